@@ -0,0 +1,304 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{DirBuilder, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    str::FromStr,
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::{config::Config, fl, utils::read_file};
+
+/// how statement text is written to audit log
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum Redact {
+    #[serde(rename = "none")]
+    None,
+    /// string and number literals are replaced with `?`, so that passwords
+    /// and personal data are not kept unless asked to
+    #[default]
+    #[serde(rename = "literals")]
+    Literals,
+}
+
+impl FromStr for Redact {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let val = match &*s.to_ascii_lowercase() {
+            "none" => Redact::None,
+            "literals" => Redact::Literals,
+            _ => return Err(anyhow!(fl!("invalid-value", val = s))),
+        };
+        Ok(val)
+    }
+}
+
+impl std::fmt::Display for Redact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let val = match self {
+            Redact::None => "none",
+            Redact::Literals => "literals",
+        };
+        write!(f, "{}", val)
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub redact: Redact,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            redact: Redact::default(),
+        }
+    }
+}
+
+/// one statement sent to server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// RFC 3339 local time the statement was sent
+    pub time: String,
+    pub user: String,
+    pub profile: String,
+    /// current database of connection, changed by `USE`
+    pub database: String,
+    pub statement: String,
+    pub duration_ms: u64,
+    pub rows_returned: u64,
+    pub rows_affected: u64,
+    pub error: Option<String>,
+}
+
+static SETTINGS: OnceCell<AuditConfig> = OnceCell::new();
+
+/// serialize appends from concurrent sessions, such as group exec
+static WRITE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// set up audit log with settings in config, called once on start
+pub fn init(config: &Config) {
+    SETTINGS.set(config.audit.clone()).ok();
+}
+
+/// directory of daily `YYYY-MM-DD.jsonl` audit files
+pub fn audit_dir() -> Result<PathBuf> {
+    let mut path = PathBuf::from(std::env::var("HOME").with_context(|| fl!("home-not-set"))?);
+    path.push(".dcli");
+    path.push("audit");
+    Ok(path)
+}
+
+fn os_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// replace quoted strings and numbers with `?`, identifiers are kept
+pub fn redact_literals(sql: &str) -> String {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut out = String::with_capacity(sql.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\'' | '"' => {
+                i += 1;
+                while i < chars.len() {
+                    if chars[i] == '\\' {
+                        i += 2;
+                        continue;
+                    }
+                    if chars[i] == c {
+                        // doubled quote is an escaped quote
+                        if chars.get(i + 1) == Some(&c) {
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    i += 1;
+                }
+                out.push('?');
+                i += 1;
+            }
+            '`' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&ch| ch == '`')
+                    .map_or(chars.len(), |n| i + n + 2);
+                out.extend(&chars[i..end.min(chars.len())]);
+                i = end;
+            }
+            '0'..='9' => {
+                let prev = if i > 0 { Some(chars[i - 1]) } else { None };
+                let in_ident = prev.is_some_and(|p| p.is_alphanumeric() || p == '_' || p == '$');
+                if in_ident {
+                    out.push(c);
+                    i += 1;
+                } else {
+                    while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                        i += 1;
+                    }
+                    out.push('?');
+                }
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn append(entry: &AuditEntry) -> Result<()> {
+    let dir = audit_dir()?;
+    let mut builder = DirBuilder::new();
+    builder.recursive(true);
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    // statements may hold sensitive data, only owner can read them
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+        builder.mode(0o700);
+        options.mode(0o600);
+    }
+    builder.create(&dir)?;
+    let file = dir.join(format!("{}.jsonl", Local::now().format("%Y-%m-%d")));
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let _lock = WRITE_LOCK.lock().unwrap();
+    options.open(&file)?.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// append statement to audit log, failing to write doesn't fail the statement
+#[allow(clippy::too_many_arguments)]
+pub fn record(
+    profile: &str,
+    database: &str,
+    statement: &str,
+    started: DateTime<Local>,
+    duration: Duration,
+    rows_returned: u64,
+    rows_affected: u64,
+    error: Option<String>,
+) {
+    let settings = SETTINGS.get_or_init(AuditConfig::default);
+    if !settings.enabled {
+        return;
+    }
+    let statement = match settings.redact {
+        Redact::None => statement.to_string(),
+        Redact::Literals => redact_literals(statement),
+    };
+    let entry = AuditEntry {
+        time: started.to_rfc3339(),
+        user: os_user(),
+        profile: profile.to_string(),
+        database: database.to_string(),
+        statement,
+        duration_ms: duration.as_millis() as u64,
+        rows_returned,
+        rows_affected,
+        error,
+    };
+    if let Err(e) = append(&entry) {
+        log::warn!("failed to write audit log: {:#}", e);
+    }
+}
+
+/// conditions of `dcli audit search`, all set ones must match
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub profile: Option<String>,
+    pub since: Option<DateTime<FixedOffset>>,
+    pub until: Option<DateTime<FixedOffset>>,
+    /// case insensitive substring of statement
+    pub text: Option<String>,
+    pub errors_only: bool,
+}
+
+impl AuditFilter {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        if let Some(profile) = &self.profile {
+            if &entry.profile != profile {
+                return false;
+            }
+        }
+        if self.since.is_some() || self.until.is_some() {
+            let time = match DateTime::parse_from_rfc3339(&entry.time) {
+                Ok(time) => time,
+                Err(_) => return false,
+            };
+            if self.since.is_some_and(|since| time < since)
+                || self.until.is_some_and(|until| time > until)
+            {
+                return false;
+            }
+        }
+        if let Some(text) = &self.text {
+            if !entry
+                .statement
+                .to_lowercase()
+                .contains(&text.to_lowercase())
+            {
+                return false;
+            }
+        }
+        !self.errors_only || entry.error.is_some()
+    }
+
+    /// skip daily files out of time range
+    fn matches_day(&self, day: NaiveDate) -> bool {
+        let since = self.since.map(|t| t.with_timezone(&Local).date_naive());
+        let until = self.until.map(|t| t.with_timezone(&Local).date_naive());
+        since.map_or(true, |since| day >= since) && until.map_or(true, |until| day <= until)
+    }
+}
+
+/// entries matching filter in time order
+pub fn search(filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+    let dir = audit_dir()?;
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut files: Vec<(NaiveDate, PathBuf)> = std::fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?;
+            let day = NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok()?;
+            Some((day, path))
+        })
+        .filter(|(day, _)| filter.matches_day(*day))
+        .collect();
+    files.sort();
+    let mut entries = vec![];
+    for (_, path) in files {
+        for line in read_file(&path.to_string_lossy())?.lines() {
+            match serde_json::from_str::<AuditEntry>(line) {
+                Ok(entry) if filter.matches(&entry) => entries.push(entry),
+                Ok(_) => {}
+                Err(e) => log::warn!("skip invalid audit entry in {:?}: {}", path, e),
+            }
+        }
+    }
+    Ok(entries)
+}
@@ -6,8 +6,9 @@ use warp::Filter;
 use warp::{http::Response, path::FullPath};
 
 use crate::{
+    erd::{Erd, ErdFormat},
     mysql::Session,
-    output::{QueryOutput, QueryOutputMapSer},
+    output::{QueryOutput, QueryOutputMapSer, Records},
     query::{Paging, QueryPlan},
 };
 
@@ -17,7 +18,7 @@ struct Asset;
 
 const CT_KEY: &str = "Content-Type";
 
-pub async fn serve(port: u16, output: QueryOutput) {
+pub async fn serve(port: u16, output: QueryOutput, erd: Erd) {
     let json_resp = output.to_json().unwrap();
     let json_resp_clone = json_resp.clone();
     let csv_resp = output.to_csv().unwrap();
@@ -56,10 +57,25 @@ pub async fn serve(port: u16, output: QueryOutput) {
         .or(data_api)
         .or(download_csv)
         .or(download_json)
-        .or(download_yaml);
+        .or(download_yaml)
+        .or(erd_api(erd));
     warp::serve(routes).run(([0, 0, 0, 0], port)).await;
 }
 
+/// serve ER diagram source at `/erd/<format>`, format is one of mermaid, dot, plantuml
+fn erd_api(erd: Erd) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::get()
+        .and(warp::path("erd"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .map(move |format: String| match format.parse::<ErdFormat>() {
+            Ok(format) => Response::builder()
+                .header(CT_KEY, "text/plain; charset=utf-8")
+                .body(erd.render(format)),
+            Err(e) => Response::builder().status(400).body(e.to_string()),
+        })
+}
+
 fn index() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     warp::get().and(warp::path::end()).map(|| {
         Response::builder()
@@ -96,9 +112,7 @@ fn assets() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection>
                 } else {
                     "text/plain"
                 };
-                Response::builder()
-                    .header(CT_KEY, ct)
-                    .body(content.to_owned())
+                Response::builder().header(CT_KEY, ct).body(content)
             } else {
                 Response::builder()
                     .status(404)
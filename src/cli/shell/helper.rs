@@ -1,29 +1,40 @@
 use colored::*;
-use sqlparser::dialect::MySqlDialect;
 use std::{
     borrow::Cow::{self, Borrowed, Owned},
     collections::{HashMap, HashSet},
 };
 
-use crate::mysql::Session;
+use crate::{
+    mysql::{Session, FUNCTIONS, KEYWORDS},
+    theme,
+};
 
-use super::highlight::{MonoKaiSchema, SQLHighLight, Schema};
+use super::{
+    highlight::highlight_sql,
+    hint::{self, ShellHint},
+    input::{self, Delimiter},
+};
 use rustyline::completion::{Completer, Pair};
 use rustyline::config::OutputStreamType;
 use rustyline::error::ReadlineError;
 use rustyline::highlight::Highlighter;
-use rustyline::hint::Hinter;
+use rustyline::hint::{Hinter, HistoryHinter};
 use rustyline::validate::{self, Validator};
-use rustyline::{CompletionType, Config, Context, EditMode, Editor};
+use rustyline::{
+    Cmd, CompletionType, ConditionalEventHandler, Config, Context, EditMode, Editor, Event,
+    EventContext, EventHandler, KeyCode, KeyEvent, Modifiers, RepeatCount,
+};
 use rustyline_derive::Helper;
 
 #[derive(Helper)]
 pub struct MyHelper {
     pub databases: HashSet<String>,
     pub tables: HashSet<String>,
-    pub columns: HashMap<String, HashSet<String>>,
+    /// column types by table and column name
+    pub columns: HashMap<String, HashMap<String, String>>,
     pub highlighter: DBHighlighter,
     pub colored_prompt: String,
+    pub delimiter: Delimiter,
 }
 
 #[derive(Debug)]
@@ -31,17 +42,7 @@ pub struct DBHighlighter {}
 
 impl Highlighter for DBHighlighter {
     fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
-        let dialect = MySqlDialect {};
-        let schema = MonoKaiSchema {};
-        let rendered = match sqlparser::tokenizer::Tokenizer::new(&dialect, line).tokenize() {
-            Ok(tokens) => tokens
-                .iter()
-                .map(|t| t.render(&schema))
-                .collect::<Vec<String>>()
-                .join(""),
-            Err(_) => line.to_string(),
-        };
-        Owned(rendered)
+        Owned(highlight_sql(line, theme::current()))
     }
 
     fn highlight_prompt<'b, 's: 'b, 'p: 'b>(
@@ -86,11 +87,23 @@ impl Completer for MyHelper {
             .last()
             .unwrap_or(&line[..pos]);
         let mut pairs: Vec<Pair> = vec![];
+        let theme = theme::current();
+
+        let upper = pattern.to_ascii_uppercase();
+        for (name, args) in FUNCTIONS.iter() {
+            if name.starts_with(&upper) {
+                pairs.push(Pair {
+                    display: format!("{} {}({})", theme.function.paint("[FN]"), name, args),
+                    replacement: format!("{}(", name),
+                })
+            }
+        }
 
-        for kw in crate::mysql::KEYWORDS.iter() {
-            if kw.starts_with(&pattern.to_ascii_uppercase()) {
+        for kw in KEYWORDS.iter() {
+            // functions are completed with their parenthesis above
+            if kw.starts_with(&upper) && !FUNCTIONS.iter().any(|(name, _)| name == kw) {
                 pairs.push(Pair {
-                    display: format!("{} {}", "[KEY]".color(MonoKaiSchema::red()), kw),
+                    display: format!("{} {}", theme.keyword.paint("[KEY]"), kw),
                     replacement: kw.to_string(),
                 })
             }
@@ -99,7 +112,7 @@ impl Completer for MyHelper {
         for db in self.databases.iter() {
             if db.contains(pattern) {
                 pairs.push(Pair {
-                    display: format!("{} {}", "[DB]".color(MonoKaiSchema::cyan()), db),
+                    display: format!("{} {}", theme.identifier.paint("[DB]"), db),
                     replacement: db.to_string(),
                 })
             }
@@ -108,21 +121,22 @@ impl Completer for MyHelper {
         for tab in self.tables.iter() {
             if tab.contains(pattern) {
                 pairs.push(Pair {
-                    display: format!("{} {}", "[TABLE]".color(MonoKaiSchema::purple()), tab),
+                    display: format!("{} {}", theme.identifier.paint("[TABLE]"), tab),
                     replacement: tab.to_string(),
                 })
             }
         }
 
         for (tab, cols) in self.columns.iter() {
-            for col in cols.iter() {
+            for (col, col_type) in cols.iter() {
                 if col.contains(pattern) {
                     pairs.push(Pair {
                         display: format!(
-                            "{} {}.{}",
-                            "[COL]".color(MonoKaiSchema::blue()),
+                            "{} {}.{} {}",
+                            theme.identifier.paint("[COL]"),
                             tab,
-                            col
+                            col,
+                            col_type
                         ),
                         replacement: col.to_string(),
                     })
@@ -136,10 +150,21 @@ impl Completer for MyHelper {
 }
 
 impl Hinter for MyHelper {
-    type Hint = String;
+    type Hint = ShellHint;
 
-    fn hint(&self, _line: &str, _pos: usize, _ctx: &Context<'_>) -> Option<Self::Hint> {
-        None
+    /// signature of function cursor is in, type of column cursor is on,
+    /// or rest of the latest history entry starting with line
+    fn hint(&self, line: &str, pos: usize, ctx: &Context<'_>) -> Option<Self::Hint> {
+        if pos < line.len() || line.starts_with('%') {
+            return None;
+        }
+        hint::signature(line)
+            .or_else(|| hint::column_type(line, &self.columns))
+            .or_else(|| {
+                HistoryHinter {}
+                    .hint(line, pos, ctx)
+                    .map(ShellHint::history)
+            })
     }
 }
 
@@ -157,7 +182,7 @@ impl Highlighter for MyHelper {
     }
 
     fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
-        Owned("\x1b[1m".to_owned() + hint + "\x1b[m")
+        Owned(theme::current().hint.paint(hint))
     }
 
     fn highlight<'l>(&self, line: &'l str, pos: usize) -> Cow<'l, str> {
@@ -174,23 +199,77 @@ impl Validator for MyHelper {
         &self,
         ctx: &mut validate::ValidationContext,
     ) -> rustyline::Result<validate::ValidationResult> {
-        let input = ctx.input();
-        if !input.starts_with('%') {
-            if input.chars().all(|c| c.is_whitespace()) || input.ends_with(';') {
-                Ok(validate::ValidationResult::Valid(None))
-            } else {
-                Ok(validate::ValidationResult::Incomplete)
-            }
-        } else {
+        if input::is_complete(ctx.input(), &self.delimiter.get()) {
             Ok(validate::ValidationResult::Valid(None))
+        } else {
+            Ok(validate::ValidationResult::Incomplete)
+        }
+    }
+}
+
+/// Enter on incomplete input starts a new line indented by open parentheses and blocks
+struct ContinuationIndent {
+    delimiter: Delimiter,
+    indent: usize,
+}
+
+impl ConditionalEventHandler for ContinuationIndent {
+    fn handle(
+        &self,
+        _evt: &Event,
+        _n: RepeatCount,
+        _positive: bool,
+        ctx: &EventContext,
+    ) -> Option<Cmd> {
+        let line = ctx.line();
+        let delimiter = self.delimiter.get();
+        if ctx.pos() != line.len() || input::is_complete(line, &delimiter) {
+            return None;
         }
+        let scanned = input::scan(line, &delimiter);
+        // spaces inside quotes or comments would change the text
+        if scanned.quote.is_some() || scanned.in_comment {
+            return Some(Cmd::Newline);
+        }
+        let depth = scanned.parens + scanned.blocks;
+        Some(Cmd::Insert(
+            1,
+            format!("\n{}", " ".repeat(depth * self.indent)),
+        ))
+    }
+}
+
+/// names for completion, loaded once per session
+#[derive(Debug, Clone, Default)]
+pub struct SchemaCache {
+    pub databases: HashSet<String>,
+    pub tables: HashSet<String>,
+    pub columns: HashMap<String, HashMap<String, String>>,
+}
+
+impl SchemaCache {
+    pub async fn load(session: &Session) -> anyhow::Result<Self> {
+        let databases = session.all_databases().await?;
+        let tables = session.all_tables().await?;
+        let columns = session.all_columns(&tables).await?;
+        Ok(Self {
+            databases,
+            tables,
+            columns,
+        })
+    }
+}
+
+impl MyHelper {
+    /// complete with names of another session
+    pub fn set_cache(&mut self, cache: &SchemaCache) {
+        self.databases = cache.databases.clone();
+        self.tables = cache.tables.clone();
+        self.columns = cache.columns.clone();
     }
 }
 
-pub async fn get_editor(session: &mut Session) -> anyhow::Result<Editor<MyHelper>> {
-    let databases = session.all_databases().await?;
-    let tables = session.all_tables().await?;
-    let columns = session.all_columns(&tables).await?;
+pub fn get_editor(cache: &SchemaCache, delimiter: &Delimiter, indent: usize) -> Editor<MyHelper> {
     let config = Config::builder()
         .history_ignore_space(true)
         .completion_type(CompletionType::List)
@@ -198,13 +277,21 @@ pub async fn get_editor(session: &mut Session) -> anyhow::Result<Editor<MyHelper
         .output_stream(OutputStreamType::Stdout)
         .build();
     let helper = MyHelper {
-        databases,
-        tables,
-        columns,
+        databases: cache.databases.clone(),
+        tables: cache.tables.clone(),
+        columns: cache.columns.clone(),
         highlighter: DBHighlighter {},
         colored_prompt: "".to_string(),
+        delimiter: delimiter.clone(),
     };
     let mut rl = Editor::with_config(config);
     rl.set_helper(Some(helper));
-    Ok(rl)
+    rl.bind_sequence(
+        KeyEvent(KeyCode::Enter, Modifiers::NONE),
+        EventHandler::Conditional(Box::new(ContinuationIndent {
+            delimiter: delimiter.clone(),
+            indent,
+        })),
+    );
+    rl
 }
@@ -1,204 +1,76 @@
-use colored::*;
 use sqlparser::{
-    dialect::keywords::Keyword,
-    tokenizer::{Token, Word},
+    dialect::{keywords::Keyword, MySqlDialect},
+    tokenizer::{Token, Tokenizer, Whitespace, Word},
 };
 
-pub trait SQLHighLight {
-    fn render<S: Schema + Copy>(&self, schema: &S) -> String;
-}
-
-impl SQLHighLight for Token {
-    fn render<S: Schema + Copy>(&self, schema: &S) -> String {
-        match self {
-            Token::EOF => "EOF".color(S::red()).to_string(),
-            Token::Word(w) => w.render(schema),
-            Token::Number(n) => n.color(S::green()).to_string(),
-            Token::Char(c) => c.to_string(),
-            Token::SingleQuotedString(s) => {
-                format!("'{}'", s.color(S::bright_yellow()).to_string())
-            }
-            Token::NationalStringLiteral(s) => {
-                format!("N'{}'", s.color(S::bright_yellow()).to_string())
+use crate::{mysql::FUNCTIONS, theme::Theme};
+
+/// render sql with theme colors, return original text if tokenize failed
+pub fn highlight_sql(sql: &str, theme: &Theme) -> String {
+    let dialect = MySqlDialect {};
+    match Tokenizer::new(&dialect, sql).tokenize() {
+        Ok(tokens) => {
+            let mut rendered = String::with_capacity(sql.len());
+            for (i, token) in tokens.iter().enumerate() {
+                match (token, tokens.get(i + 1)) {
+                    // name followed by `(` is a call
+                    (Token::Word(w), Some(Token::LParen)) if is_function(w) => {
+                        rendered.push_str(&theme.function.paint(&w.to_string()))
+                    }
+                    _ => rendered.push_str(&token.render(theme)),
+                }
             }
-            Token::HexStringLiteral(s) => format!("X'{}'", s.color(S::bright_yellow()).to_string()),
-            _ => self.to_string(),
+            rendered
         }
+        Err(_) => sql.to_string(),
     }
 }
 
-impl SQLHighLight for Word {
-    fn render<S: Schema + Copy>(&self, _schema: &S) -> String {
-        match self.keyword {
-            Keyword::NoKeyword => self.to_string().color(S::blue()).to_string(),
-            _ => self.value.color(S::green()).to_string(),
-        }
+/// built-in function or user defined one, keywords such as `IN (` are not
+fn is_function(word: &Word) -> bool {
+    if word.quote_style.is_some() {
+        return false;
     }
+    let name = word.value.to_ascii_uppercase();
+    word.keyword == Keyword::NoKeyword || FUNCTIONS.iter().any(|(f, _)| *f == name)
 }
 
-pub trait Schema {
-    fn black() -> Color;
-    fn red() -> Color;
-    fn green() -> Color;
-    fn yellow() -> Color;
-    fn blue() -> Color;
-    fn purple() -> Color;
-    fn cyan() -> Color;
-    fn white() -> Color;
-    fn bright_black() -> Color;
-    fn bright_red() -> Color;
-    fn bright_green() -> Color;
-    fn bright_yellow() -> Color;
-    fn bright_blue() -> Color;
-    fn bright_purple() -> Color;
-    fn bright_cyan() -> Color;
-    fn bright_white() -> Color;
-    fn background() -> Color;
-    fn foreground() -> Color;
+pub trait SQLHighLight {
+    fn render(&self, theme: &Theme) -> String;
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct MonoKaiSchema;
-
-// TODO use macro to reduce code
-impl Schema for MonoKaiSchema {
-    fn black() -> Color {
-        Color::TrueColor { r: 0, g: 0, b: 0 }
-    }
-
-    fn red() -> Color {
-        Color::TrueColor {
-            r: 216,
-            g: 30,
-            b: 0,
-        }
-    }
-
-    fn green() -> Color {
-        Color::TrueColor {
-            r: 94,
-            g: 167,
-            b: 2,
-        }
-    }
-
-    fn yellow() -> Color {
-        Color::TrueColor {
-            r: 207,
-            g: 174,
-            b: 0,
-        }
-    }
-
-    fn blue() -> Color {
-        Color::TrueColor {
-            r: 66,
-            g: 122,
-            b: 179,
-        }
-    }
-
-    fn purple() -> Color {
-        Color::TrueColor {
-            r: 137,
-            g: 101,
-            b: 142,
-        }
-    }
-
-    fn cyan() -> Color {
-        Color::TrueColor {
-            r: 0,
-            g: 167,
-            b: 170,
-        }
-    }
-
-    fn white() -> Color {
-        Color::TrueColor {
-            r: 219,
-            g: 222,
-            b: 216,
-        }
-    }
-
-    fn bright_black() -> Color {
-        Color::TrueColor {
-            r: 104,
-            g: 106,
-            b: 102,
-        }
-    }
-
-    fn bright_red() -> Color {
-        Color::TrueColor {
-            r: 245,
-            g: 66,
-            b: 53,
-        }
-    }
-
-    fn bright_green() -> Color {
-        Color::TrueColor {
-            r: 253,
-            g: 235,
-            b: 97,
-        }
-    }
-
-    fn bright_yellow() -> Color {
-        Color::TrueColor {
-            r: 253,
-            g: 235,
-            b: 97,
-        }
-    }
-
-    fn bright_blue() -> Color {
-        Color::TrueColor {
-            r: 132,
-            g: 176,
-            b: 216,
-        }
-    }
-
-    fn bright_purple() -> Color {
-        Color::TrueColor {
-            r: 188,
-            g: 148,
-            b: 183,
-        }
-    }
-
-    fn bright_cyan() -> Color {
-        Color::TrueColor {
-            r: 55,
-            g: 230,
-            b: 232,
-        }
-    }
-
-    fn bright_white() -> Color {
-        Color::TrueColor {
-            r: 241,
-            g: 241,
-            b: 240,
+impl SQLHighLight for Token {
+    fn render(&self, theme: &Theme) -> String {
+        match self {
+            Token::EOF => String::new(),
+            Token::Word(w) => w.render(theme),
+            Token::Number(n) => theme.number.paint(n),
+            Token::Char(c) => c.to_string(),
+            Token::SingleQuotedString(_)
+            | Token::NationalStringLiteral(_)
+            | Token::HexStringLiteral(_) => theme.string.paint(&self.to_string()),
+            Token::Whitespace(ws) => ws.render(theme),
+            _ => theme.operator.paint(&self.to_string()),
         }
     }
+}
 
-    fn background() -> Color {
-        Color::TrueColor {
-            r: 40,
-            g: 42,
-            b: 58,
+impl SQLHighLight for Word {
+    fn render(&self, theme: &Theme) -> String {
+        match self.keyword {
+            Keyword::NoKeyword => theme.identifier.paint(&self.to_string()),
+            _ => theme.keyword.paint(&self.to_string()),
         }
     }
+}
 
-    fn foreground() -> Color {
-        Color::TrueColor {
-            r: 234,
-            g: 242,
-            b: 241,
+impl SQLHighLight for Whitespace {
+    fn render(&self, theme: &Theme) -> String {
+        match self {
+            Whitespace::SingleLineComment(_) | Whitespace::MultiLineComment(_) => {
+                theme.comment.paint(&self.to_string())
+            }
+            _ => self.to_string(),
         }
     }
 }
@@ -0,0 +1,165 @@
+use rustyline::hint::Hint;
+use std::collections::HashMap;
+
+use crate::mysql::FUNCTIONS;
+
+/// grey text after cursor, only history hints can be accepted with right arrow
+pub struct ShellHint {
+    display: String,
+    completion: Option<String>,
+}
+
+impl ShellHint {
+    /// rest of a history entry, accepted with right arrow
+    pub fn history(rest: String) -> Self {
+        Self {
+            display: rest.clone(),
+            completion: Some(rest),
+        }
+    }
+
+    fn note(display: String) -> Self {
+        Self {
+            display,
+            completion: None,
+        }
+    }
+}
+
+impl Hint for ShellHint {
+    fn display(&self) -> &str {
+        &self.display
+    }
+
+    fn completion(&self) -> Option<&str> {
+        self.completion.as_deref()
+    }
+}
+
+/// split arguments of signature by top level commas, `[, locale]` stays in one part
+fn split_args(args: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !args.trim().is_empty() {
+        parts.push(args[start..].trim());
+    }
+    parts
+}
+
+/// trailing chars of text matching `is_word`
+fn last_word(text: &str, is_word: impl Fn(char) -> bool) -> &str {
+    let start = text
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !is_word(*c))
+        .map(|(i, c)| i + c.len_utf8())
+        .unwrap_or(0);
+    &text[start..]
+}
+
+/// byte offset of innermost unclosed `(` and commas typed after it, quotes are skipped
+fn open_call(line: &str) -> Option<(usize, usize)> {
+    let mut calls: Vec<(usize, usize)> = vec![];
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '(') => calls.push((i, 0)),
+            (None, ')') => {
+                calls.pop();
+            }
+            (None, ',') => {
+                if let Some(call) = calls.last_mut() {
+                    call.1 += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    calls.pop()
+}
+
+/// remaining arguments of the built-in function the cursor is in, such as `, format)`
+/// after `DATE_FORMAT(created_at`
+pub fn signature(line: &str) -> Option<ShellHint> {
+    let (paren, commas) = open_call(line)?;
+    let before = line[..paren].trim_end();
+    let name = last_word(before, |c| c.is_alphanumeric() || c == '_').to_ascii_uppercase();
+    let (_, args) = FUNCTIONS.iter().find(|(f, _)| *f == name)?;
+    let parts = split_args(args);
+    if commas > parts.len() || (commas == parts.len() && !parts.is_empty()) {
+        return None;
+    }
+    let typed = line[paren + 1..].rsplit(',').next().unwrap_or_default();
+    let rest = if typed.trim().is_empty() {
+        // a space after `,` unless typed
+        let lead = if commas > 0 && typed.is_empty() {
+            " "
+        } else {
+            ""
+        };
+        format!("{}{})", lead, parts[commas..].join(", "))
+    } else if commas + 1 < parts.len() {
+        format!(", {})", parts[commas + 1..].join(", "))
+    } else {
+        ")".to_string()
+    };
+    Some(ShellHint::note(rest))
+}
+
+/// type of the known column cursor is on, such as `  bigint unsigned`,
+/// a name found in several tables shows the type of each
+pub fn column_type(
+    line: &str,
+    columns: &HashMap<String, HashMap<String, String>>,
+) -> Option<ShellHint> {
+    let word = last_word(line, |c| {
+        c.is_alphanumeric() || matches!(c, '_' | '.' | '`')
+    });
+    let word = word.replace('`', "");
+    let (table, column) = match word.rsplit_once('.') {
+        Some((table, column)) => (Some(table), column),
+        None => (None, word.as_str()),
+    };
+    if column.is_empty() {
+        return None;
+    }
+    if let Some(col_type) = table
+        .and_then(|t| columns.get(t))
+        .and_then(|cols| cols.get(column))
+    {
+        return Some(ShellHint::note(format!("  {}", col_type)));
+    }
+    let mut found: Vec<(&String, &String)> = columns
+        .iter()
+        .filter_map(|(t, cols)| cols.get(column).map(|col_type| (t, col_type)))
+        .collect();
+    found.sort();
+    match found[..] {
+        [] => None,
+        [(_, col_type)] => Some(ShellHint::note(format!("  {}", col_type))),
+        _ => {
+            let types: Vec<String> = found
+                .iter()
+                .take(3)
+                .map(|(t, col_type)| format!("{}: {}", t, col_type))
+                .collect();
+            let more = if found.len() > 3 { ", ..." } else { "" };
+            Some(ShellHint::note(format!("  {}{}", types.join(", "), more)))
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use anyhow::{anyhow, Context, Result};
+use std::{
+    io::Write,
+    sync::{Arc, RwLock},
+};
+
+use super::redirect::{parse_redirect, Target};
+use crate::{fl, utils::read_file};
+
+/// statement delimiter changed by `DELIMITER`, shared by validator, Enter key and shell
+#[derive(Debug, Clone)]
+pub struct Delimiter(Arc<RwLock<String>>);
+
+impl Default for Delimiter {
+    fn default() -> Self {
+        Self(Arc::new(RwLock::new(";".to_string())))
+    }
+}
+
+impl Delimiter {
+    pub fn get(&self) -> String {
+        self.0.read().unwrap().clone()
+    }
+
+    pub fn set(&self, delimiter: &str) {
+        *self.0.write().unwrap() = delimiter.to_string();
+    }
+}
+
+/// words opening a compound statement inside BEGIN ... END, closed by `END <word>`
+const BLOCK_WORDS: &[&str] = &["IF", "LOOP", "REPEAT", "WHILE"];
+
+/// objects of `CREATE` whose body may be a BEGIN ... END block
+const ROUTINE_WORDS: &[&str] = &["EVENT", "FUNCTION", "PROCEDURE", "TRIGGER"];
+
+/// words after which a statement starts inside BEGIN ... END
+const STATEMENT_LEADS: &[&str] = &["BEGIN", "DO", "ELSE", "LOOP", "REPEAT", "THEN"];
+
+/// input split into statements by delimiter or `\G`
+#[derive(Debug, Default)]
+pub struct Scanned {
+    /// statements without delimiter
+    pub statements: Vec<String>,
+    /// text after the last delimiter
+    pub rest: String,
+    /// rest has code other than comments
+    rest_has_code: bool,
+    /// the last statement ends with `\G`, its result is shown vertically
+    pub vertical: bool,
+    /// quote of unterminated string or identifier
+    pub quote: Option<char>,
+    /// inside unterminated `/* */` comment
+    pub in_comment: bool,
+    /// unclosed parentheses of rest
+    pub parens: usize,
+    /// unclosed BEGIN ... END, CASE ... END and the like of rest
+    pub blocks: usize,
+}
+
+impl Scanned {
+    /// nothing but comments or a redirect follows the last delimiter
+    pub fn is_complete(&self) -> bool {
+        self.quote.is_none()
+            && !self.in_comment
+            && (!self.rest_has_code || self.redirect().is_some())
+    }
+
+    /// `> path` or `>> path` after the last statement
+    pub fn redirect(&self) -> Option<Target> {
+        if self.statements.is_empty() {
+            return None;
+        }
+        parse_redirect(&self.rest)
+    }
+
+    /// statements to send to server one by one, an unterminated one is included
+    pub fn to_exec(&self) -> Vec<String> {
+        let mut statements = self.statements.clone();
+        if self.rest_has_code && self.redirect().is_none() {
+            statements.push(self.rest.trim().to_string());
+        }
+        statements
+    }
+}
+
+/// split input by delimiter, `;` inside BEGIN ... END doesn't end a statement
+/// unless it is the delimiter, quotes and comments are skipped
+pub fn scan(input: &str, delimiter: &str) -> Scanned {
+    let chars: Vec<(usize, char)> = input.char_indices().collect();
+    let offset = |i: usize| chars.get(i).map(|(o, _)| *o).unwrap_or(input.len());
+    let mut scanned = Scanned::default();
+    let mut start = 0;
+    let mut statement_start = true;
+    let mut after_end = false;
+    // BEGIN opens a block only in the body of a routine, otherwise it starts
+    // a transaction or is a name, such as `SELECT begin FROM t`
+    let mut create = false;
+    let mut routine = false;
+    let mut after_label = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].1;
+        let next = chars.get(i + 1).map(|(_, c)| *c);
+        let at = chars[i].0;
+        if let Some(quote) = scanned.quote {
+            if c == '\\' && quote != '`' {
+                i += 1;
+            } else if c == quote {
+                // doubled quote is an escaped quote
+                if next == Some(quote) {
+                    i += 1;
+                } else {
+                    scanned.quote = None;
+                }
+            }
+            i += 1;
+            continue;
+        }
+        if scanned.in_comment {
+            if c == '*' && next == Some('/') {
+                scanned.in_comment = false;
+                i += 1;
+            }
+            i += 1;
+            continue;
+        }
+        let terminator =
+            if input[at..].starts_with(delimiter) && (delimiter != ";" || scanned.blocks == 0) {
+                Some(delimiter.chars().count())
+            } else if c == '\\' && matches!(next, Some('G') | Some('g')) {
+                Some(2)
+            } else {
+                None
+            };
+        if let Some(len) = terminator {
+            let statement = input[start..at].trim();
+            if scanned.rest_has_code {
+                scanned.statements.push(statement.to_string());
+            }
+            scanned.vertical = next == Some('G') && c == '\\';
+            i += len;
+            start = offset(i);
+            scanned.rest_has_code = false;
+            scanned.parens = 0;
+            scanned.blocks = 0;
+            statement_start = true;
+            after_end = false;
+            create = false;
+            routine = false;
+            after_label = false;
+            continue;
+        }
+        match c {
+            _ if c.is_whitespace() => {}
+            '#' => {
+                while i < chars.len() && chars[i].1 != '\n' {
+                    i += 1;
+                }
+            }
+            '-' if next == Some('-')
+                && chars.get(i + 2).map_or(true, |(_, c)| c.is_whitespace()) =>
+            {
+                while i < chars.len() && chars[i].1 != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if next == Some('*') => {
+                // executable comment such as `/*!40101 SET NAMES utf8mb4 */` is code
+                if chars.get(i + 2).map(|(_, c)| *c) == Some('!') {
+                    scanned.rest_has_code = true;
+                }
+                scanned.in_comment = true;
+                i += 1;
+            }
+            '\'' | '"' | '`' => {
+                scanned.quote = Some(c);
+                scanned.rest_has_code = true;
+                statement_start = false;
+            }
+            _ if c.is_alphanumeric() || c == '_' || c == '$' => {
+                while i + 1 < chars.len()
+                    && (chars[i + 1].1.is_alphanumeric() || matches!(chars[i + 1].1, '_' | '$'))
+                {
+                    i += 1;
+                }
+                let word = input[at..offset(i + 1)].to_ascii_uppercase();
+                if !scanned.rest_has_code {
+                    create = word == "CREATE";
+                } else if create && ROUTINE_WORDS.contains(&&*word) {
+                    routine = true;
+                }
+                let opens = word == "CASE"
+                    || (word == "BEGIN"
+                        && routine
+                        && (scanned.blocks > 0 || !statement_start || after_label))
+                    || (BLOCK_WORDS.contains(&&*word) && scanned.blocks > 0 && statement_start);
+                if after_end && (word == "CASE" || BLOCK_WORDS.contains(&&*word)) {
+                    // `END IF` and the like close one block only
+                } else if word == "END" {
+                    scanned.blocks = scanned.blocks.saturating_sub(1);
+                } else if opens {
+                    scanned.blocks += 1;
+                }
+                after_end = word == "END";
+                after_label = false;
+                statement_start = STATEMENT_LEADS.contains(&&*word);
+                scanned.rest_has_code = true;
+            }
+            _ => {
+                match c {
+                    '(' => scanned.parens += 1,
+                    ')' => scanned.parens = scanned.parens.saturating_sub(1),
+                    _ => {}
+                }
+                // `;` inside a block and labels such as `l1:` start a statement
+                after_label = c == ':' && next != Some('=');
+                statement_start = c == ';' || after_label;
+                after_end = false;
+                scanned.rest_has_code = true;
+            }
+        }
+        i += 1;
+    }
+    scanned.rest = input[start..].to_string();
+    scanned
+}
+
+/// new delimiter of client command `DELIMITER //`
+pub fn parse_delimiter(line: &str) -> Option<&str> {
+    let mut words = line.split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+        (Some(command), Some(delimiter), None) if command.eq_ignore_ascii_case("delimiter") => {
+            Some(delimiter)
+        }
+        _ => None,
+    }
+}
+
+/// leading options of builtin arguments and the raw text after them, `with_value`
+/// lists options followed by a value, which may be quoted such as `-u "count = 0"`
+pub fn split_options<'a>(args: &'a str, with_value: &[&str]) -> (Vec<String>, &'a str) {
+    let mut options = vec![];
+    let mut rest = args.trim_start();
+    let mut expect_value = false;
+    while !rest.is_empty() && (expect_value || rest.starts_with('-')) {
+        let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"');
+        let (arg, remaining) = match quote {
+            Some(quote) => match rest[1..].find(quote) {
+                Some(end) => (&rest[1..end + 1], &rest[end + 2..]),
+                None => (&rest[1..], ""),
+            },
+            None => rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len())),
+        };
+        rest = remaining.trim_start();
+        if !expect_value && arg == "--" {
+            break;
+        }
+        expect_value = !expect_value && with_value.contains(&arg);
+        options.push(arg.to_string());
+    }
+    (options, rest)
+}
+
+/// lines before `%edit` if it's the last line of input
+pub fn split_edit(input: &str) -> Option<&str> {
+    let input = input.trim_end();
+    let (buffer, last) = match input.rfind('\n') {
+        Some(i) => (&input[..i], &input[i + 1..]),
+        None => ("", input),
+    };
+    if last.trim() == "%edit" {
+        Some(buffer)
+    } else {
+        None
+    }
+}
+
+/// input can be run, builtins and client commands are always complete
+pub fn is_complete(input: &str, delimiter: &str) -> bool {
+    input.starts_with('%')
+        || parse_delimiter(input).is_some()
+        || split_edit(input).is_some()
+        || scan(input, delimiter).is_complete()
+}
+
+/// edit text in `$VISUAL` or `$EDITOR`, vi by default, return the saved text
+pub fn edit(text: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // created with a random name and only owner can access it, removed when dropped
+    let mut temp = tempfile::Builder::new()
+        .prefix("dcli-")
+        .suffix(".sql")
+        .tempfile()
+        .with_context(|| fl!("write-file-failed", file = "dcli-*.sql"))?;
+    let path = temp.path().to_path_buf();
+    let file = path.to_string_lossy().to_string();
+    temp.write_all(text.as_bytes())
+        .and_then(|_| temp.flush())
+        .with_context(|| fl!("write-file-failed", file = file.clone()))?;
+    // editor may carry arguments, such as `code --wait`
+    let mut args = editor.split_whitespace();
+    let program = args.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(args)
+        .arg(&path)
+        .status()
+        .with_context(|| fl!("editor-failed", editor = editor.clone()))?;
+    if !status.success() {
+        return Err(anyhow!(fl!("editor-failed", editor = editor)));
+    }
+    // editors may save by replacing the file, so read it again by path
+    let edited = read_file(&file)?;
+    Ok(edited.trim_end().to_string())
+}
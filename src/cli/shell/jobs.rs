@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Result};
+use std::time::Instant;
+use tokio::{sync::oneshot, task::JoinHandle};
+
+use crate::{config::Config, fl, mysql::Session, output::QueryOutput};
+
+/// query started by `%bg`
+struct Job {
+    id: usize,
+    profile: String,
+    sql: String,
+    started: Instant,
+    receiver: oneshot::Receiver<Result<QueryOutput>>,
+    /// set once the query returns
+    result: Option<Result<QueryOutput>>,
+    /// kill the query on server, dropping it does too
+    cancel: Option<oneshot::Sender<()>>,
+    handle: JoinHandle<()>,
+}
+
+impl Job {
+    fn poll(&mut self) {
+        if self.result.is_none() {
+            if let Ok(result) = self.receiver.try_recv() {
+                self.result = Some(result);
+            }
+        }
+    }
+}
+
+/// background queries of shell, each on its own connection
+#[derive(Default)]
+pub struct Jobs {
+    next_id: usize,
+    jobs: Vec<Job>,
+}
+
+impl Jobs {
+    /// start query in background, return its job id
+    pub fn spawn(&mut self, profile: &str, session: Session, sql: String) -> usize {
+        self.next_id += 1;
+        let (sender, receiver) = oneshot::channel();
+        let (cancel, cancelled) = oneshot::channel::<()>();
+        let to_exec = sql.clone();
+        let handle = tokio::spawn(async move {
+            let cancelled = async {
+                cancelled.await.ok();
+            };
+            sender
+                .send(session.query_detached(&to_exec, cancelled).await)
+                .ok();
+        });
+        self.jobs.push(Job {
+            id: self.next_id,
+            profile: profile.to_string(),
+            sql,
+            started: Instant::now(),
+            receiver,
+            result: None,
+            cancel: Some(cancel),
+            handle,
+        });
+        self.next_id
+    }
+
+    /// ids of jobs finished since last call, to notify at prompt
+    pub fn newly_finished(&mut self) -> Vec<usize> {
+        self.jobs
+            .iter_mut()
+            .filter(|job| job.result.is_none())
+            .filter_map(|job| {
+                job.poll();
+                job.result.as_ref().map(|_| job.id)
+            })
+            .collect()
+    }
+
+    pub fn print(&mut self, config: &Config) {
+        let mut table = config.new_table();
+        table.set_header(vec!["id", "profile", "state", "elapsed", "sql"]);
+        for job in self.jobs.iter_mut() {
+            job.poll();
+            let state = match &job.result {
+                None => "running",
+                Some(Ok(_)) => "done",
+                Some(Err(_)) => "failed",
+            };
+            table.add_row(vec![
+                job.id.to_string(),
+                job.profile.clone(),
+                state.to_string(),
+                format!("{:.1}s", job.started.elapsed().as_secs_f64()),
+                job.sql.clone(),
+            ]);
+        }
+        println!("{}", table);
+    }
+
+    /// wait for job, the latest one if id is not set, and take its result,
+    /// Ctrl-C stops waiting and leaves job running
+    pub async fn fg(&mut self, id: Option<usize>) -> Result<(String, Result<QueryOutput>)> {
+        let index = match id {
+            Some(id) => self.jobs.iter().position(|job| job.id == id),
+            None => self.jobs.len().checked_sub(1),
+        }
+        .ok_or_else(|| anyhow!(fl!("job-not-found", id = id.unwrap_or_default())))?;
+        let job = &mut self.jobs[index];
+        if job.result.is_none() {
+            tokio::select! {
+                result = &mut job.receiver => {
+                    job.result = Some(result.unwrap_or_else(|e| Err(e.into())));
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    return Err(anyhow!(fl!("job-still-running", id = job.id)));
+                }
+            }
+        }
+        let job = self.jobs.remove(index);
+        Ok((job.sql, job.result.unwrap()))
+    }
+
+    /// kill running jobs on server and wait for them, called when shell exits
+    pub async fn abort_all(&mut self) {
+        for job in self.jobs.iter_mut() {
+            job.poll();
+            if job.result.is_none() {
+                if let Some(cancel) = job.cancel.take() {
+                    cancel.send(()).ok();
+                }
+            }
+        }
+        for job in self.jobs.iter_mut() {
+            (&mut job.handle).await.ok();
+        }
+    }
+}
+
+/// wait for Ctrl-C, to cancel running statement
+pub async fn ctrl_c() {
+    tokio::signal::ctrl_c().await.ok();
+}
@@ -1,13 +1,35 @@
-use crate::{config::Config, utils::read_file};
+use super::SchemaCmd;
+use crate::{
+    config::{Config, SafetyMode},
+    formatter::format_sql,
+    guard,
+    history::{self, HistoryOptions},
+    snippet,
+    template::render,
+    theme,
+    utils::{read_file, split_sql},
+    watch::{watch, WatchOptions},
+};
 use crate::{fl, mysql::Session};
-use anyhow::Context;
-use colored::*;
-use highlight::{MonoKaiSchema, Schema};
+use chrono::Local;
+use helper::MyHelper;
+use highlight::highlight_sql;
+use input::Delimiter;
+use jobs::Jobs;
+use redirect::{Outputs, Target};
 use rustyline::error::ReadlineError;
+use rustyline::Editor;
+use sessions::{Sessions, ShellSession};
+use std::{collections::HashMap, time::Instant};
 use structopt::StructOpt;
 
 mod helper;
-mod highlight;
+pub mod highlight;
+mod hint;
+mod input;
+mod jobs;
+mod redirect;
+mod sessions;
 
 #[derive(Debug)]
 pub struct Shell;
@@ -27,10 +49,71 @@ pub enum BuiltIn {
     #[structopt(name = "%help")]
     Help,
 
-    #[cfg_attr(feature = "zh-CN", doc = "查看历史")]
-    #[cfg_attr(feature = "en-US", doc = "list history")]
+    #[cfg_attr(feature = "zh-CN", doc = "查看当前配置的历史, 或搜索及重新执行历史")]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "list history of current profile, or search and rerun history"
+    )]
     #[structopt(name = "%his")]
-    His,
+    His {
+        #[structopt(subcommand)]
+        cmd: Option<HisCmd>,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "连接另一个配置并切换到该会话")]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "connect to another profile and switch to the session"
+    )]
+    #[structopt(name = "%connect")]
+    Connect {
+        #[cfg_attr(feature = "zh-CN", doc = "连接配置名称")]
+        #[cfg_attr(feature = "en-US", doc = "profile name")]
+        profile: String,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "切换到已连接的会话")]
+    #[cfg_attr(feature = "en-US", doc = "switch to a connected session")]
+    #[structopt(name = "%switch")]
+    Switch {
+        #[cfg_attr(feature = "zh-CN", doc = "连接配置名称")]
+        #[cfg_attr(feature = "en-US", doc = "profile name")]
+        profile: String,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "列出已连接的会话")]
+    #[cfg_attr(feature = "en-US", doc = "list connected sessions")]
+    #[structopt(name = "%sessions")]
+    Sessions,
+
+    #[cfg_attr(feature = "zh-CN", doc = "在后台使用独立连接执行查询, 不在当前事务中")]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "run query in background on its own connection, outside of current transaction"
+    )]
+    #[structopt(name = "%bg")]
+    Bg {
+        #[cfg_attr(feature = "zh-CN", doc = "SQL 语句")]
+        #[cfg_attr(feature = "en-US", doc = "sql")]
+        sql: String,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "列出后台查询")]
+    #[cfg_attr(feature = "en-US", doc = "list background queries")]
+    #[structopt(name = "%jobs")]
+    Jobs,
+
+    #[cfg_attr(feature = "zh-CN", doc = "等待后台查询并显示结果, Ctrl-C 停止等待")]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "wait for background query and show its result, Ctrl-C to stop waiting"
+    )]
+    #[structopt(name = "%fg")]
+    Fg {
+        #[cfg_attr(feature = "zh-CN", doc = "任务编号, 默认为最近一个")]
+        #[cfg_attr(feature = "en-US", doc = "job id, the latest one by default")]
+        id: Option<usize>,
+    },
 
     #[cfg_attr(feature = "zh-CN", doc = "运行 SQL 文件")]
     #[cfg_attr(feature = "en-US", doc = "exec SQL file")]
@@ -40,29 +123,316 @@ pub enum BuiltIn {
         #[cfg_attr(feature = "en-US", doc = "path file")]
         path: String,
     },
+
+    #[cfg_attr(
+        feature = "zh-CN",
+        doc = "将下一个查询结果写入文件, 格式由扩展名推断, 未知扩展名写入表格"
+    )]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "write next query result to file, format is inferred from extension, table for unknown ones"
+    )]
+    #[structopt(name = "%out")]
+    Out {
+        #[structopt(flatten)]
+        target: TargetArgs,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "将之后所有查询结果同时写入文件, 直到 %notee")]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "also write all following query results to file, until %notee"
+    )]
+    #[structopt(name = "%tee")]
+    Tee {
+        #[structopt(flatten)]
+        target: TargetArgs,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "停止写入 %tee 文件")]
+    #[cfg_attr(feature = "en-US", doc = "stop writing to %tee file")]
+    #[structopt(name = "%notee")]
+    NoTee,
+
+    #[cfg_attr(feature = "zh-CN", doc = "设置变量, 在 SQL 中使用 ${name} 引用")]
+    #[cfg_attr(feature = "en-US", doc = "set variable, referenced as ${name} in SQL")]
+    #[structopt(name = "%set")]
+    Set {
+        #[cfg_attr(feature = "zh-CN", doc = "变量名")]
+        #[cfg_attr(feature = "en-US", doc = "variable name")]
+        name: String,
+
+        #[cfg_attr(feature = "zh-CN", doc = "变量值")]
+        #[cfg_attr(feature = "en-US", doc = "variable value")]
+        #[structopt(required = true)]
+        value: Vec<String>,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "删除变量")]
+    #[cfg_attr(feature = "en-US", doc = "remove variable")]
+    #[structopt(name = "%unset")]
+    Unset {
+        #[cfg_attr(feature = "zh-CN", doc = "变量名")]
+        #[cfg_attr(feature = "en-US", doc = "variable name")]
+        name: String,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "列出所有变量")]
+    #[cfg_attr(feature = "en-US", doc = "list variables")]
+    #[structopt(name = "%vars")]
+    Vars,
+
+    #[cfg_attr(feature = "zh-CN", doc = "运行已保存的查询片段")]
+    #[cfg_attr(feature = "en-US", doc = "run a saved snippet")]
+    #[structopt(name = "%snippet")]
+    Snippet {
+        #[cfg_attr(feature = "zh-CN", doc = "片段名称")]
+        #[cfg_attr(feature = "en-US", doc = "snippet name")]
+        name: String,
+
+        #[cfg_attr(feature = "zh-CN", doc = "参数, 如 days=7, 缺少的参数会提示输入")]
+        #[cfg_attr(
+            feature = "en-US",
+            doc = "parameters such as days=7, missing ones are prompted"
+        )]
+        params: Vec<String>,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "列出所有表及其引擎, 行数估计和大小")]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "list tables with engine, estimated rows and sizes"
+    )]
+    #[structopt(name = "%tables")]
+    Tables {
+        #[cfg_attr(feature = "zh-CN", doc = "表名匹配模式, 如 user%")]
+        #[cfg_attr(feature = "en-US", doc = "table name LIKE pattern, such as user%")]
+        pattern: Option<String>,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "查看表字段")]
+    #[cfg_attr(feature = "en-US", doc = "show table columns")]
+    #[structopt(name = "%describe")]
+    Describe {
+        #[cfg_attr(feature = "zh-CN", doc = "表名")]
+        #[cfg_attr(feature = "en-US", doc = "table name")]
+        table: String,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "查看表索引")]
+    #[cfg_attr(feature = "en-US", doc = "show table indexes")]
+    #[structopt(name = "%indexes")]
+    Indexes {
+        #[cfg_attr(feature = "zh-CN", doc = "表名")]
+        #[cfg_attr(feature = "en-US", doc = "table name")]
+        table: String,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "查看建表语句")]
+    #[cfg_attr(feature = "en-US", doc = "show create table statement")]
+    #[structopt(name = "%ddl")]
+    Ddl {
+        #[cfg_attr(feature = "zh-CN", doc = "表名")]
+        #[cfg_attr(feature = "en-US", doc = "table name")]
+        table: String,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "查看表的外键(引用和被引用)")]
+    #[cfg_attr(feature = "en-US", doc = "show inbound and outbound foreign keys")]
+    #[structopt(name = "%fk")]
+    Fk {
+        #[cfg_attr(feature = "zh-CN", doc = "表名")]
+        #[cfg_attr(feature = "en-US", doc = "table name")]
+        table: String,
+    },
+
+    #[cfg_attr(feature = "zh-CN", doc = "定时重复执行查询并高亮变化, Ctrl-C 停止")]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "re-run a query periodically and highlight changes, Ctrl-C to stop"
+    )]
+    #[structopt(name = "%watch")]
+    Watch {
+        #[structopt(flatten)]
+        opts: WatchOptions,
+
+        #[cfg_attr(feature = "zh-CN", doc = "SQL 语句")]
+        #[cfg_attr(feature = "en-US", doc = "sql")]
+        sql: String,
+    },
+
+    #[cfg_attr(
+        feature = "zh-CN",
+        doc = "跳过写入确认执行 SQL, 允许没有 WHERE 或 LIMIT 的 UPDATE 和 DELETE"
+    )]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "run sql without write confirmation, allow UPDATE and DELETE without WHERE or LIMIT"
+    )]
+    #[structopt(name = "%force")]
+    Force {
+        #[cfg_attr(feature = "zh-CN", doc = "SQL 语句")]
+        #[cfg_attr(feature = "en-US", doc = "sql")]
+        sql: String,
+    },
+
+    #[cfg_attr(
+        feature = "zh-CN",
+        doc = "在 $EDITOR 中编辑当前输入或上一条语句, 也可作为多行输入的最后一行"
+    )]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "edit current input or the last statement in $EDITOR, also works as the last line of multi-line input"
+    )]
+    #[structopt(name = "%edit")]
+    Edit,
+
+    #[cfg_attr(feature = "zh-CN", doc = "按配置格式化 SQL, 不执行")]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "pretty print sql with fmt settings, without running it"
+    )]
+    #[structopt(name = "%fmt")]
+    Fmt {
+        #[cfg_attr(feature = "zh-CN", doc = "SQL 语句")]
+        #[cfg_attr(feature = "en-US", doc = "sql")]
+        sql: String,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TargetArgs {
+    #[cfg_attr(
+        feature = "zh-CN",
+        doc = "格式: csv, json, yaml, toml, pickle, 只有一个参数时为文件路径"
+    )]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "format: csv, json, yaml, toml, pickle, file path if it's the only argument"
+    )]
+    first: String,
+
+    #[cfg_attr(feature = "zh-CN", doc = "文件路径")]
+    #[cfg_attr(feature = "en-US", doc = "file path")]
+    path: Option<String>,
+
+    #[cfg_attr(feature = "zh-CN", doc = "追加到文件而非覆盖")]
+    #[cfg_attr(feature = "en-US", doc = "append to file instead of overwriting")]
+    #[structopt(short, long)]
+    append: bool,
+}
+
+impl TargetArgs {
+    fn target(&self) -> anyhow::Result<Target> {
+        Target::from_args(&self.first, self.path.as_deref(), self.append)
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub enum HisCmd {
+    #[cfg_attr(feature = "zh-CN", doc = "模糊搜索所有配置的历史")]
+    #[cfg_attr(feature = "en-US", doc = "fuzzy search history of all profiles")]
+    Search {
+        #[structopt(flatten)]
+        opts: HistoryOptions,
+    },
+    #[cfg_attr(feature = "zh-CN", doc = "在当前会话中重新执行一条历史")]
+    #[cfg_attr(feature = "en-US", doc = "rerun a history entry in current session")]
+    Rerun {
+        #[cfg_attr(feature = "zh-CN", doc = "历史列表中的序号")]
+        #[cfg_attr(feature = "en-US", doc = "index in history list")]
+        index: usize,
+    },
 }
 
 impl Shell {
     pub async fn run(config: &mut Config, profile: &str) -> anyhow::Result<()> {
-        let profile = config.try_get_profile(profile)?;
-        let history = profile.load_or_create_history()?;
-        let mut session = Session::connect_with(&profile).await?;
-        let mut rl = helper::get_editor(&mut session).await?;
+        let mut sessions = Sessions::new(ShellSession::connect(config, profile).await?);
+        let delimiter = Delimiter::default();
+        let mut rl = helper::get_editor(&sessions.active().cache, &delimiter, config.fmt.indent);
+        // text edited by `%edit`, put in the next prompt to review and run
+        let mut pending: Option<String> = None;
         let mut count: usize = 1;
-        rl.load_history(&history)
-            .with_context(|| fl!("load-his-failed"))?;
+        let mut vars: HashMap<String, String> = HashMap::new();
+        let mut outputs = Outputs::default();
+        let mut jobs = Jobs::default();
+        Shell::activate(&mut rl, sessions.active(), config.recall_failed)?;
         loop {
-            let p = format!("[{}]: ", count)
-                .color(MonoKaiSchema::green())
-                .to_string();
+            for id in jobs.newly_finished() {
+                println!("{}", fl!("job-done", id = id));
+            }
+            let active = sessions.active();
+            let session = active.session.clone();
+            let history = active.history.clone();
+            // guarded profiles, such as production ones, get a red prompt
+            let theme = theme::current();
+            let prompt_color = match session.mode() {
+                SafetyMode::Unrestricted => theme.prompt,
+                _ => theme.prompt_guarded,
+            };
+            let transaction = if active.in_transaction { "*" } else { "" };
+            let p = prompt_color.paint(&format!("[{}{}:{}]: ", active.profile, transaction, count));
             rl.helper_mut().unwrap().colored_prompt = p.clone();
-            let input = rl.readline(&p);
+            let input = match pending.take() {
+                Some(text) => rl.readline_with_initial(&p, (&text, "")),
+                None => rl.readline(&p),
+            };
             match input {
                 Ok(line) => {
                     if !line.is_empty() {
+                        // a rerun entry is handled as if it was typed
+                        let line = match Shell::take_builtin(&line) {
+                            Ok(Some(BuiltIn::His {
+                                cmd: Some(HisCmd::Rerun { index }),
+                            })) => match history::get(index).and_then(|entry| {
+                                println!("{}", entry.statement);
+                                // an entry of another profile, such as a production one,
+                                // may not be meant for this session
+                                let profile = &sessions.active().profile;
+                                if &entry.profile != profile
+                                    && !guard::confirm(&fl!(
+                                        "his-rerun-other-profile",
+                                        recorded = entry.profile.as_str(),
+                                        profile = profile.as_str()
+                                    ))?
+                                {
+                                    return Err(anyhow::anyhow!(fl!("guard-cancelled")));
+                                }
+                                Ok(entry)
+                            }) {
+                                Ok(entry) => entry.statement,
+                                Err(e) => {
+                                    println!("{:?}", e);
+                                    count += 1;
+                                    continue;
+                                }
+                            },
+                            _ => line,
+                        };
+                        // `%edit` as the last line edits lines before it, or the last statement
+                        if let Some(buffer) = input::split_edit(&line) {
+                            let text = match buffer.trim() {
+                                "" => rl.history().last().cloned().unwrap_or_default(),
+                                _ => buffer.to_string(),
+                            };
+                            match input::edit(&text) {
+                                Ok(edited) => pending = Some(edited),
+                                Err(e) => println!("{:?}", e),
+                            }
+                            count += 1;
+                            continue;
+                        }
+                        if let Some(new_delimiter) = input::parse_delimiter(&line) {
+                            delimiter.set(new_delimiter);
+                            rl.add_history_entry(line.as_str());
+                            count += 1;
+                            continue;
+                        }
                         match Shell::take_builtin(&line) {
                             Ok(maybe_builtin) => {
                                 if let Some(builtin) = maybe_builtin {
+                                    let started = Local::now();
+                                    let timer = Instant::now();
                                     match builtin {
                                         BuiltIn::Exit => {
                                             println!("Exit...");
@@ -71,19 +441,244 @@ impl Shell {
                                         BuiltIn::Help => {
                                             BuiltIn::clap().print_help().unwrap();
                                         }
-                                        BuiltIn::His => {
-                                            rl.history()
-                                                .iter()
-                                                .enumerate()
-                                                .for_each(|(i, h)| println!("{} {}", i, h));
+                                        BuiltIn::His { cmd } => {
+                                            let opts = match cmd {
+                                                None => HistoryOptions {
+                                                    profile: Some(
+                                                        sessions.active().profile.clone(),
+                                                    ),
+                                                    since: None,
+                                                    until: None,
+                                                    all: true,
+                                                    limit: 50,
+                                                    text: None,
+                                                },
+                                                Some(HisCmd::Search { opts }) => opts,
+                                                Some(HisCmd::Rerun { .. }) => {
+                                                    println!("{}", fl!("his-rerun-nested"));
+                                                    count += 1;
+                                                    continue;
+                                                }
+                                            };
+                                            match history::search(&opts) {
+                                                Ok(entries) => history::print(config, entries),
+                                                Err(e) => println!("{:?}", e),
+                                            }
+                                        }
+                                        BuiltIn::Connect { profile } => {
+                                            match sessions.open(config, &profile).await {
+                                                Ok(_) => {
+                                                    sessions.switch(&profile)?;
+                                                    Shell::activate(
+                                                        &mut rl,
+                                                        sessions.active(),
+                                                        config.recall_failed,
+                                                    )?;
+                                                }
+                                                Err(e) => println!("{:?}", e),
+                                            }
                                         }
-                                        BuiltIn::Run { path } => match read_file(&path) {
+                                        BuiltIn::Switch { profile } => {
+                                            match sessions.switch(&profile) {
+                                                Ok(_) => Shell::activate(
+                                                    &mut rl,
+                                                    sessions.active(),
+                                                    config.recall_failed,
+                                                )?,
+                                                Err(e) => println!("{:?}", e),
+                                            }
+                                        }
+                                        BuiltIn::Sessions => sessions.print(config),
+                                        BuiltIn::Bg { sql } => match render(&sql, &vars) {
+                                            Ok(sql) => {
+                                                match guard::check(&session, &sql, false).await {
+                                                    Ok(_) => {
+                                                        let id = jobs.spawn(
+                                                            &sessions.active().profile,
+                                                            session.clone(),
+                                                            sql,
+                                                        );
+                                                        println!("{}", fl!("job-started", id = id));
+                                                    }
+                                                    Err(e) => println!("{:?}", e),
+                                                }
+                                            }
+                                            Err(e) => {
+                                                println!("{:?}", e);
+                                            }
+                                        },
+                                        BuiltIn::Jobs => jobs.print(config),
+                                        BuiltIn::Fg { id } => match jobs.fg(id).await {
+                                            Ok((sql, Ok(output))) => {
+                                                println!("{}", sql);
+                                                if let Err(e) =
+                                                    outputs.show(config, &output, None, false)
+                                                {
+                                                    println!("{:?}", e);
+                                                }
+                                            }
+                                            Ok((sql, Err(e))) => {
+                                                println!("{}", sql);
+                                                println!("Server Err: {}", e);
+                                            }
+                                            Err(e) => println!("{:?}", e),
+                                        },
+                                        BuiltIn::Out { target } => match target.target() {
+                                            Ok(target) => outputs.redirect_next(target),
+                                            Err(e) => println!("{:?}", e),
+                                        },
+                                        BuiltIn::Tee { target } => {
+                                            if let Err(e) = target
+                                                .target()
+                                                .and_then(|target| outputs.tee(Some(target)))
+                                            {
+                                                println!("{:?}", e);
+                                            }
+                                        }
+                                        BuiltIn::NoTee => outputs.tee(None)?,
+                                        BuiltIn::Set { name, value } => {
+                                            vars.insert(name, value.join(" "));
+                                        }
+                                        BuiltIn::Unset { name } => {
+                                            if vars.remove(&name).is_none() {
+                                                println!("{}", fl!("var-not-set", name = name));
+                                            }
+                                        }
+                                        // handled before builtins are parsed
+                                        BuiltIn::Edit => {}
+                                        BuiltIn::Fmt { sql } => {
+                                            let formatted = format_sql(&sql, &config.fmt);
+                                            println!("{}", highlight_sql(&formatted, theme));
+                                        }
+                                        BuiltIn::Vars => {
+                                            let mut table = config.new_table();
+                                            table.set_header(vec!["name", "value"]);
+                                            let mut names: Vec<&String> = vars.keys().collect();
+                                            names.sort();
+                                            for name in names {
+                                                table.add_row(vec![name, &vars[name]]);
+                                            }
+                                            println!("{}", table);
+                                        }
+                                        BuiltIn::Run { path } => match read_file(&path)
+                                            .and_then(|content| render(&content, &vars))
+                                        {
                                             Ok(content) => {
-                                                for sql in content.split(';') {
-                                                    if !sql.is_empty() {
-                                                        let output = session.query(sql).await?;
-                                                        output.to_print_table(&config, false);
+                                                for sql in split_sql(&content) {
+                                                    if let Err(e) =
+                                                        guard::check(&session, &sql, false).await
+                                                    {
+                                                        println!("{:?}", e);
+                                                        break;
+                                                    }
+                                                    let output = match session
+                                                        .query_or_cancel(&sql, jobs::ctrl_c())
+                                                        .await
+                                                    {
+                                                        Ok(output) => output,
+                                                        Err(e) => {
+                                                            println!("Server Err: {}", e);
+                                                            break;
+                                                        }
+                                                    };
+                                                    sessions.active_mut().track_transaction(&sql);
+                                                    if let Err(e) =
+                                                        outputs.show(config, &output, None, false)
+                                                    {
+                                                        println!("{:?}", e);
+                                                    }
+                                                }
+                                            }
+                                            Err(e) => {
+                                                println!("{:?}", e);
+                                            }
+                                        },
+                                        BuiltIn::Snippet { name, params } => {
+                                            match snippet::call(config, &name, &params, &vars) {
+                                                Ok(content) => {
+                                                    for sql in split_sql(&content) {
+                                                        if let Err(e) =
+                                                            guard::check(&session, &sql, false)
+                                                                .await
+                                                        {
+                                                            println!("{:?}", e);
+                                                            break;
+                                                        }
+                                                        match session
+                                                            .query_or_cancel(&sql, jobs::ctrl_c())
+                                                            .await
+                                                        {
+                                                            Ok(output) => {
+                                                                sessions
+                                                                    .active_mut()
+                                                                    .track_transaction(&sql);
+                                                                if let Err(e) = outputs.show(
+                                                                    config, &output, None, false,
+                                                                ) {
+                                                                    println!("{:?}", e);
+                                                                }
+                                                            }
+                                                            Err(e) => {
+                                                                println!("Server Err: {}", e);
+                                                                break;
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                Err(e) => {
+                                                    println!("{:?}", e);
+                                                }
+                                            }
+                                        }
+                                        BuiltIn::Tables { pattern } => {
+                                            let cmd = SchemaCmd::Tables { pattern };
+                                            Shell::run_schema(config, &session, cmd).await;
+                                        }
+                                        BuiltIn::Describe { table } => {
+                                            let cmd = SchemaCmd::Describe { table };
+                                            Shell::run_schema(config, &session, cmd).await;
+                                        }
+                                        BuiltIn::Indexes { table } => {
+                                            let cmd = SchemaCmd::Indexes { table };
+                                            Shell::run_schema(config, &session, cmd).await;
+                                        }
+                                        BuiltIn::Ddl { table } => {
+                                            let cmd = SchemaCmd::Ddl { table };
+                                            Shell::run_schema(config, &session, cmd).await;
+                                        }
+                                        BuiltIn::Fk { table } => {
+                                            let cmd = SchemaCmd::Fk { table };
+                                            Shell::run_schema(config, &session, cmd).await;
+                                        }
+                                        BuiltIn::Watch { opts, sql } => match render(&sql, &vars) {
+                                            Ok(sql) => {
+                                                let sql = sql.trim().trim_end_matches(';');
+                                                if let Err(e) = async {
+                                                    guard::check(&session, sql, false).await?;
+                                                    watch(config, &session, sql, &opts).await
+                                                }
+                                                .await
+                                                {
+                                                    println!("{:?}", e);
+                                                }
+                                            }
+                                            Err(e) => {
+                                                println!("{:?}", e);
+                                            }
+                                        },
+                                        BuiltIn::Force { sql } => match render(&sql, &vars) {
+                                            Ok(sql) => {
+                                                match guard::check(&session, &sql, true).await {
+                                                    Ok(_) => {
+                                                        Shell::run_forced(
+                                                            config,
+                                                            &mut sessions,
+                                                            &mut outputs,
+                                                            &sql,
+                                                        )
+                                                        .await
                                                     }
+                                                    Err(e) => println!("{:?}", e),
                                                 }
                                             }
                                             Err(e) => {
@@ -91,14 +686,106 @@ impl Shell {
                                             }
                                         },
                                     }
+                                    // history commands are not recorded so that reruns stay stable
+                                    if !line.starts_with("%his") {
+                                        history.record(&line, started, timer.elapsed(), true, 0);
+                                    }
                                     rl.add_history_entry(line.as_str());
                                 } else {
-                                    match session.query(&line).await {
-                                        Ok(output) => {
-                                            output.to_print_table(config, false);
+                                    // `@profile` runs the line in another session, connecting if needed
+                                    let (target, rest) = sessions::split_target(&line);
+                                    let index = match target {
+                                        Some(name) => match sessions.open(config, name).await {
+                                            Ok(index) => index,
+                                            Err(e) => {
+                                                println!("{:?}", e);
+                                                count += 1;
+                                                continue;
+                                            }
+                                        },
+                                        None => sessions.active_index(),
+                                    };
+                                    let session = sessions.get(index).session.clone();
+                                    let history = sessions.get(index).history.clone();
+                                    let scanned = input::scan(rest, &delimiter.get());
+                                    let mut redirect = scanned.redirect();
+                                    let statements = match scanned
+                                        .to_exec()
+                                        .iter()
+                                        .map(|sql| render(sql, &vars))
+                                        .collect::<anyhow::Result<Vec<String>>>()
+                                    {
+                                        Ok(statements) => statements,
+                                        Err(e) => {
+                                            println!("{:?}", e);
+                                            count += 1;
+                                            continue;
+                                        }
+                                    };
+                                    // all statements are checked before any of them runs
+                                    let mut checked = Ok(());
+                                    for sql in statements.iter() {
+                                        checked = guard::check(&session, sql, false).await;
+                                        if checked.is_err() {
+                                            break;
+                                        }
+                                    }
+                                    if let Err(e) = checked {
+                                        println!("{:?}", e);
+                                        count += 1;
+                                        continue;
+                                    }
+                                    let started = Local::now();
+                                    let timer = Instant::now();
+                                    let mut rows = 0;
+                                    let mut failed = None;
+                                    // each statement is sent alone, the redirect and `\G`
+                                    // apply to the last one
+                                    for (i, sql) in statements.iter().enumerate() {
+                                        let last = i + 1 == statements.len();
+                                        match session.query_or_cancel(sql, jobs::ctrl_c()).await {
+                                            Ok(output) => {
+                                                sessions.get_mut(index).track_transaction(sql);
+                                                rows += output.rows.len() as u64;
+                                                let (redirect, vertical) = if last {
+                                                    (redirect.take(), scanned.vertical)
+                                                } else {
+                                                    (None, false)
+                                                };
+                                                if let Err(e) = outputs
+                                                    .show(config, &output, redirect, vertical)
+                                                {
+                                                    println!("{:?}", e);
+                                                }
+                                            }
+                                            Err(e) => {
+                                                failed = Some(e);
+                                                break;
+                                            }
+                                        }
+                                    }
+                                    match failed {
+                                        None => {
+                                            history.record(
+                                                &line,
+                                                started,
+                                                timer.elapsed(),
+                                                true,
+                                                rows,
+                                            );
                                             rl.add_history_entry(line.as_str());
                                         }
-                                        Err(e) => {
+                                        Some(e) => {
+                                            history.record(
+                                                &line,
+                                                started,
+                                                timer.elapsed(),
+                                                false,
+                                                rows,
+                                            );
+                                            if config.recall_failed {
+                                                rl.add_history_entry(line.as_str());
+                                            }
                                             println!("Server Err: {}", e)
                                         }
                                     }
@@ -122,12 +809,35 @@ impl Shell {
             }
             count += 1;
         }
-        session.close().await;
-        rl.append_history(&history).unwrap();
+        jobs.abort_all().await;
+        sessions.close_all().await;
         Ok(())
     }
 
+    /// recall history and complete names of session
+    fn activate(
+        rl: &mut Editor<MyHelper>,
+        current: &ShellSession,
+        recall_failed: bool,
+    ) -> anyhow::Result<()> {
+        rl.clear_history();
+        for line in current.history.recall(recall_failed)? {
+            rl.add_history_entry(line);
+        }
+        rl.helper_mut().unwrap().set_cache(&current.cache);
+        Ok(())
+    }
+
+    async fn run_schema(config: &Config, session: &Session, cmd: SchemaCmd) {
+        if let Err(e) = cmd.run(config, session).await {
+            println!("{:?}", e);
+        }
+    }
+
     fn take_builtin(line: &str) -> anyhow::Result<Option<BuiltIn>> {
+        if let Some(builtin) = Shell::take_raw_builtin(line)? {
+            return Ok(Some(builtin));
+        }
         if line.starts_with('%') {
             let builtin =
                 BuiltIn::from_iter_safe(format!("builtin {}", line).split_ascii_whitespace())
@@ -143,4 +853,61 @@ impl Shell {
             Ok(None)
         }
     }
+
+    /// run statements of `%force` one by one like typed ones, stop at the first error
+    async fn run_forced(
+        config: &Config,
+        sessions: &mut Sessions,
+        outputs: &mut Outputs,
+        sql: &str,
+    ) {
+        let session = sessions.active().session.clone();
+        for sql in split_sql(sql) {
+            match session.query_or_cancel(&sql, jobs::ctrl_c()).await {
+                Ok(output) => {
+                    sessions.active_mut().track_transaction(&sql);
+                    if let Err(e) = outputs.show(config, &output, None, false) {
+                        println!("{:?}", e);
+                    }
+                }
+                Err(e) => {
+                    println!("{:?}", e);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// builtins taking SQL as the raw rest of line, so that whitespace in literals
+    /// is kept and values such as `-1` are not taken as options
+    fn take_raw_builtin(line: &str) -> anyhow::Result<Option<BuiltIn>> {
+        let line = line.trim();
+        let (name, rest) = match line.find(char::is_whitespace) {
+            Some(end) => line.split_at(end),
+            None => return Ok(None),
+        };
+        // options of `%watch` come first, SQL tokens such as `-1` are not options
+        let (options, sql) = match name {
+            "%watch" => input::split_options(rest, &["-n", "--interval", "-u", "--until"]),
+            "%bg" | "%force" | "%fmt" => (vec![], rest.trim()),
+            _ => return Ok(None),
+        };
+        let sql = sql.trim().to_string();
+        // missing SQL and `--help` are reported by structopt
+        if sql.is_empty() || sql == "-h" || sql == "--help" {
+            return Ok(None);
+        }
+        let builtin = match name {
+            "%watch" => BuiltIn::Watch {
+                opts: WatchOptions::from_iter_safe(
+                    std::iter::once(name.to_string()).chain(options),
+                )?,
+                sql,
+            },
+            "%bg" => BuiltIn::Bg { sql },
+            "%fmt" => BuiltIn::Fmt { sql },
+            _ => BuiltIn::Force { sql },
+        };
+        Ok(Some(builtin))
+    }
 }
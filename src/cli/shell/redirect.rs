@@ -0,0 +1,145 @@
+use anyhow::{anyhow, Context, Result};
+use std::{fs::OpenOptions, io::Write, path::PathBuf};
+
+use crate::{
+    config::Config,
+    fl,
+    output::{Format, QueryOutput},
+};
+
+/// file query results are written to, as table text if format is unknown
+#[derive(Debug)]
+pub struct Target {
+    path: PathBuf,
+    format: Option<Format>,
+    append: bool,
+}
+
+impl Target {
+    /// format is inferred from file extension if not set
+    pub fn new(path: &str, format: Option<Format>, append: bool) -> Self {
+        let path = PathBuf::from(path);
+        let format = format.or_else(|| Format::from_path(&path));
+        Self {
+            path,
+            format,
+            append,
+        }
+    }
+
+    /// parse builtin arguments `[FORMAT] PATH`
+    pub fn from_args(first: &str, second: Option<&str>, append: bool) -> Result<Self> {
+        match second {
+            Some(path) => Ok(Self::new(path, Some(first.parse()?), append)),
+            None => Ok(Self::new(first, None, append)),
+        }
+    }
+
+    /// only line oriented output can be appended, a JSON, YAML, TOML or pickle
+    /// document appended to another one makes the file invalid
+    fn check_appendable(&self) -> Result<()> {
+        match self.format {
+            None | Some(Format::Csv) => Ok(()),
+            Some(_) => Err(anyhow!(fl!(
+                "out-not-appendable",
+                file = self.path.to_string_lossy().to_string()
+            ))),
+        }
+    }
+
+    /// file is truncated on first write unless appending, later writes append to it,
+    /// CSV header is skipped when appending to a file which is not empty
+    fn write(&mut self, config: &Config, output: &QueryOutput) -> Result<()> {
+        if self.append {
+            self.check_appendable()?;
+        }
+        let content = match &self.format {
+            Some(format) => format.to_bytes(output)?,
+            None => output.to_table_string(config).into_bytes(),
+        };
+        let has_content =
+            self.append && std::fs::metadata(&self.path).is_ok_and(|meta| meta.len() > 0);
+        let content = match (&self.format, content.iter().position(|b| *b == b'\n')) {
+            (Some(Format::Csv), Some(end)) if has_content => content[end + 1..].to_vec(),
+            _ => content,
+        };
+        let file = self.path.to_string_lossy().to_string();
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(self.append)
+            .truncate(!self.append)
+            .open(&self.path)
+            .with_context(|| fl!("open-file-failed", file = file.clone()))?
+            .write_all(&content)
+            .with_context(|| fl!("write-file-failed", file = file))?;
+        self.append = true;
+        Ok(())
+    }
+}
+
+/// where shell sends query results besides terminal
+#[derive(Debug, Default)]
+pub struct Outputs {
+    /// set by `%out`, takes the next result only
+    next: Option<Target>,
+    /// set by `%tee`, copy of every result until `%notee`
+    tee: Option<Target>,
+}
+
+impl Outputs {
+    pub fn redirect_next(&mut self, target: Target) {
+        self.next = Some(target);
+    }
+
+    /// every result is appended to tee file, so it must be line oriented
+    pub fn tee(&mut self, target: Option<Target>) -> Result<()> {
+        if let Some(target) = &target {
+            target.check_appendable()?;
+        }
+        self.tee = target;
+        Ok(())
+    }
+
+    /// print result, vertically if asked by `\G`, or write it to file if redirected
+    /// by line or `%out`, it is also written to tee file if set
+    pub fn show(
+        &mut self,
+        config: &Config,
+        output: &QueryOutput,
+        redirect: Option<Target>,
+        vertical: bool,
+    ) -> Result<()> {
+        match redirect.or_else(|| self.next.take()) {
+            Some(mut target) => {
+                target.write(config, output)?;
+                println!(
+                    "{}",
+                    fl!(
+                        "out-written",
+                        rows = output.rows.len(),
+                        file = target.path.to_string_lossy().to_string()
+                    )
+                );
+            }
+            None => output.to_print_table(config, vertical),
+        }
+        if let Some(tee) = &mut self.tee {
+            tee.write(config, output)?;
+        }
+        Ok(())
+    }
+}
+
+/// parse `> path` or `>> path` following the last statement, `>>` appends to file
+pub fn parse_redirect(rest: &str) -> Option<Target> {
+    let rest = rest.trim().strip_prefix('>')?;
+    let (path, append) = match rest.strip_prefix('>') {
+        Some(path) => (path.trim(), true),
+        None => (rest.trim(), false),
+    };
+    if path.is_empty() {
+        return None;
+    }
+    Some(Target::new(path, None, append))
+}
@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Result};
+
+use super::helper::SchemaCache;
+use crate::{config::Config, fl, history::History, mysql::Session, utils::split_sql};
+
+/// connection opened in shell, with its own history, completion names and transaction state
+pub struct ShellSession {
+    pub profile: String,
+    pub session: Session,
+    pub history: History,
+    pub cache: SchemaCache,
+    pub in_transaction: bool,
+}
+
+impl ShellSession {
+    pub async fn connect(config: &Config, name: &str) -> Result<Self> {
+        let profile = config.try_get_profile(name)?.clone();
+        let history = History::open(&profile)?;
+        // statements of a transaction must go to the same connection
+        let session = Session::connect_dedicated(&profile).await?;
+        let cache = SchemaCache::load(&session).await?;
+        Ok(Self {
+            profile: profile.name,
+            session,
+            history,
+            cache,
+            in_transaction: false,
+        })
+    }
+
+    /// follow BEGIN, COMMIT and ROLLBACK in statements run successfully
+    pub fn track_transaction(&mut self, sql: &str) {
+        for statement in split_sql(sql) {
+            let words: Vec<String> = statement
+                .split_whitespace()
+                .take(2)
+                .map(|w| w.trim_end_matches(';').to_ascii_uppercase())
+                .collect();
+            match words.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+                ["BEGIN", ..] | ["START", "TRANSACTION"] => self.in_transaction = true,
+                ["ROLLBACK", "TO"] => {}
+                ["COMMIT", ..] | ["ROLLBACK", ..] => self.in_transaction = false,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// sessions opened in shell, one of them is active
+pub struct Sessions {
+    sessions: Vec<ShellSession>,
+    active: usize,
+}
+
+impl Sessions {
+    pub fn new(first: ShellSession) -> Self {
+        Self {
+            sessions: vec![first],
+            active: 0,
+        }
+    }
+
+    pub fn active(&self) -> &ShellSession {
+        &self.sessions[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut ShellSession {
+        &mut self.sessions[self.active]
+    }
+
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    pub fn get(&self, index: usize) -> &ShellSession {
+        &self.sessions[index]
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> &mut ShellSession {
+        &mut self.sessions[index]
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.sessions.iter().position(|s| s.profile == name)
+    }
+
+    /// connect to profile unless it's opened, return its index
+    pub async fn open(&mut self, config: &Config, name: &str) -> Result<usize> {
+        if let Some(index) = self.find(name) {
+            return Ok(index);
+        }
+        self.sessions
+            .push(ShellSession::connect(config, name).await?);
+        Ok(self.sessions.len() - 1)
+    }
+
+    pub fn switch(&mut self, name: &str) -> Result<()> {
+        self.active = self
+            .find(name)
+            .ok_or_else(|| anyhow!(fl!("session-not-opened", name = name)))?;
+        Ok(())
+    }
+
+    pub fn print(&self, config: &Config) {
+        let mut table = config.new_table();
+        table.set_header(vec!["", "profile", "mode", "transaction"]);
+        for (i, s) in self.sessions.iter().enumerate() {
+            let active = if i == self.active { "*" } else { "" };
+            let transaction = if s.in_transaction { "open" } else { "" };
+            table.add_row(vec![
+                active.to_string(),
+                s.profile.clone(),
+                s.session.mode().to_string(),
+                transaction.to_string(),
+            ]);
+        }
+        println!("{}", table);
+    }
+
+    pub async fn close_all(&self) {
+        for s in self.sessions.iter() {
+            s.session.close().await;
+        }
+    }
+}
+
+/// split leading `@profile` from line, such as `@staging SELECT 1;`
+pub fn split_target(line: &str) -> (Option<&str>, &str) {
+    match line.strip_prefix('@') {
+        Some(rest) => {
+            let rest = rest.trim_start();
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            (Some(&rest[..end]), rest[end..].trim_start())
+        }
+        None => (None, line),
+    }
+}
@@ -0,0 +1,275 @@
+use anyhow::anyhow;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    str::FromStr,
+};
+
+use crate::fl;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum CodeLang {
+    #[serde(rename = "rust")]
+    Rust,
+    #[serde(rename = "typescript")]
+    TypeScript,
+    #[serde(rename = "python")]
+    Python,
+}
+
+impl FromStr for CodeLang {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let val = match &*s.to_ascii_lowercase() {
+            "rust" | "rs" => CodeLang::Rust,
+            "typescript" | "ts" => CodeLang::TypeScript,
+            "python" | "py" => CodeLang::Python,
+            _ => return Err(anyhow!(fl!("invalid-value", val = s))),
+        };
+        Ok(val)
+    }
+}
+
+/// column name, sqlx type name and nullability of a result column
+#[derive(Debug, Clone)]
+pub struct ColumnMeta {
+    pub name: String,
+    pub type_name: String,
+    pub nullable: bool,
+}
+
+const RUST_KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "unsafe",
+    "use", "where", "while",
+];
+
+const PYTHON_KEYWORDS: [&str; 35] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// convert column name to snake case identifier, `Order Id` -> `order_id`
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && prev_lower {
+                out.push('_');
+            }
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+            out.push(c.to_ascii_lowercase());
+        } else {
+            if !out.ends_with('_') {
+                out.push('_');
+            }
+            prev_lower = false;
+        }
+    }
+    let out = out.trim_matches('_').to_string();
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", out)
+    } else {
+        out
+    }
+}
+
+/// convert table name to type name, `order_items` -> `OrderItems`
+fn pascal_case(name: &str) -> String {
+    snake_case(name)
+        .split('_')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut chars = s.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn is_js_ident(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// map sqlx type name to rust type, ref `DCliColumn::serialize`
+fn rust_type(type_name: &str) -> &'static str {
+    match type_name {
+        "BOOLEAN" => "bool",
+        "TINYINT UNSIGNED" => "u8",
+        "SMALLINT UNSIGNED" => "u16",
+        "INT UNSIGNED" | "MEDIUMINT UNSIGNED" => "u32",
+        "BIGINT UNSIGNED" => "u64",
+        "TINYINT" => "i8",
+        "SMALLINT" => "i16",
+        "INT" | "MEDIUMINT" => "i32",
+        "BIGINT" => "i64",
+        "FLOAT" => "f32",
+        "DOUBLE" => "f64",
+        "DATE" => "chrono::NaiveDate",
+        "TIME" => "chrono::NaiveTime",
+        "YEAR" => "u16",
+        "DATETIME" => "chrono::NaiveDateTime",
+        "TIMESTAMP" => "chrono::DateTime<chrono::Utc>",
+        "DECIMAL" => "sqlx::types::BigDecimal",
+        "JSON" => "serde_json::Value",
+        "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BINARY" | "VARBINARY" => "Vec<u8>",
+        _ => "String",
+    }
+}
+
+/// map sqlx type name to typescript type, follow json output of dcli
+fn ts_type(type_name: &str) -> &'static str {
+    match type_name {
+        "BOOLEAN" => "boolean",
+        "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "INT UNSIGNED" | "MEDIUMINT UNSIGNED"
+        | "BIGINT UNSIGNED" | "TINYINT" | "SMALLINT" | "INT" | "MEDIUMINT" | "BIGINT" | "FLOAT"
+        | "DOUBLE" | "YEAR" => "number",
+        "JSON" => "unknown",
+        _ => "string",
+    }
+}
+
+/// map sqlx type name to python type and the module it should be imported from
+fn py_type(type_name: &str) -> (&'static str, Option<&'static str>) {
+    match type_name {
+        "BOOLEAN" => ("bool", None),
+        "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "INT UNSIGNED" | "MEDIUMINT UNSIGNED"
+        | "BIGINT UNSIGNED" | "TINYINT" | "SMALLINT" | "INT" | "MEDIUMINT" | "BIGINT" | "YEAR" => {
+            ("int", None)
+        }
+        "FLOAT" | "DOUBLE" => ("float", None),
+        "DATE" => ("date", Some("datetime")),
+        "TIME" => ("time", Some("datetime")),
+        "DATETIME" | "TIMESTAMP" => ("datetime", Some("datetime")),
+        "DECIMAL" => ("Decimal", Some("decimal")),
+        "JSON" => ("Any", Some("typing")),
+        "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BINARY" | "VARBINARY" => ("bytes", None),
+        _ => ("str", None),
+    }
+}
+
+pub fn generate(lang: CodeLang, name: &str, columns: &[ColumnMeta], pydantic: bool) -> String {
+    match lang {
+        CodeLang::Rust => to_rust(name, columns),
+        CodeLang::TypeScript => to_typescript(name, columns),
+        CodeLang::Python => to_python(name, columns, pydantic),
+    }
+}
+
+fn to_rust(name: &str, columns: &[ColumnMeta]) -> String {
+    let mut out = String::new();
+    out.push_str("#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize, serde::Deserialize)]\n");
+    writeln!(out, "pub struct {} {{", pascal_case(name)).unwrap();
+    for col in columns {
+        let mut field = snake_case(&col.name);
+        if field != col.name {
+            writeln!(out, "    #[sqlx(rename = \"{}\")]", col.name).unwrap();
+            writeln!(out, "    #[serde(rename = \"{}\")]", col.name).unwrap();
+        }
+        if RUST_KEYWORDS.contains(&field.as_str()) {
+            field = format!("r#{}", field);
+        }
+        let ty = rust_type(&col.type_name);
+        if col.nullable {
+            writeln!(out, "    pub {}: Option<{}>,", field, ty).unwrap();
+        } else {
+            writeln!(out, "    pub {}: {},", field, ty).unwrap();
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn to_typescript(name: &str, columns: &[ColumnMeta]) -> String {
+    let mut out = String::new();
+    writeln!(out, "export interface {} {{", pascal_case(name)).unwrap();
+    for col in columns {
+        let key = if is_js_ident(&col.name) {
+            col.name.clone()
+        } else {
+            format!("\"{}\"", col.name.replace('"', "\\\""))
+        };
+        let ty = ts_type(&col.type_name);
+        if col.nullable {
+            writeln!(out, "  {}: {} | null;", key, ty).unwrap();
+        } else {
+            writeln!(out, "  {}: {};", key, ty).unwrap();
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn to_python(name: &str, columns: &[ColumnMeta], pydantic: bool) -> String {
+    // module -> imported names
+    let mut imports: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    let mut fields = String::new();
+    for col in columns {
+        let (ty, module) = py_type(&col.type_name);
+        if let Some(module) = module {
+            imports.entry(module).or_default().insert(ty);
+        }
+        let ty = if col.nullable {
+            imports.entry("typing").or_default().insert("Optional");
+            format!("Optional[{}]", ty)
+        } else {
+            ty.to_string()
+        };
+        let mut field = snake_case(&col.name);
+        if PYTHON_KEYWORDS.contains(&field.as_str()) {
+            field.push('_');
+        }
+        if field == col.name {
+            writeln!(fields, "    {}: {}", field, ty).unwrap();
+        } else if pydantic {
+            imports.entry("pydantic").or_default().insert("Field");
+            writeln!(
+                fields,
+                "    {}: {} = Field(alias=\"{}\")",
+                field, ty, col.name
+            )
+            .unwrap();
+        } else {
+            writeln!(fields, "    {}: {}  # column `{}`", field, ty, col.name).unwrap();
+        }
+    }
+    if pydantic {
+        imports.entry("pydantic").or_default().insert("BaseModel");
+    } else {
+        imports
+            .entry("dataclasses")
+            .or_default()
+            .insert("dataclass");
+    }
+
+    let mut out = String::new();
+    for (module, names) in imports.iter() {
+        let names = names.iter().copied().collect::<Vec<&str>>().join(", ");
+        writeln!(out, "from {} import {}", module, names).unwrap();
+    }
+    out.push_str("\n\n");
+    if pydantic {
+        writeln!(out, "class {}(BaseModel):", pascal_case(name)).unwrap();
+    } else {
+        out.push_str("@dataclass\n");
+        writeln!(out, "class {}:", pascal_case(name)).unwrap();
+    }
+    if columns.is_empty() {
+        out.push_str("    pass\n");
+    } else {
+        out.push_str(&fields);
+    }
+    out
+}
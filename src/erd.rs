@@ -0,0 +1,248 @@
+use anyhow::anyhow;
+use serde::Serialize;
+use std::{fmt::Write, str::FromStr};
+
+use crate::fl;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum ErdFormat {
+    #[serde(rename = "mermaid")]
+    Mermaid,
+    #[serde(rename = "dot")]
+    Dot,
+    #[serde(rename = "plantuml")]
+    PlantUml,
+}
+
+impl FromStr for ErdFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let val = match &*s.to_ascii_lowercase() {
+            "mermaid" => ErdFormat::Mermaid,
+            "dot" | "graphviz" => ErdFormat::Dot,
+            "plantuml" => ErdFormat::PlantUml,
+            _ => return Err(anyhow!(fl!("invalid-value", val = s))),
+        };
+        Ok(val)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErdColumn {
+    pub name: String,
+    pub data_type: String,
+    pub primary: bool,
+    pub nullable: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErdTable {
+    pub name: String,
+    pub columns: Vec<ErdColumn>,
+}
+
+/// foreign key from `table.columns` to `ref_table.ref_columns`
+#[derive(Debug, Clone, Serialize)]
+pub struct ErdRelation {
+    pub constraint: String,
+    pub table: String,
+    pub columns: Vec<String>,
+    pub ref_table: String,
+    pub ref_columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Erd {
+    pub tables: Vec<ErdTable>,
+    pub relations: Vec<ErdRelation>,
+}
+
+/// replace chars which are not allowed in diagram identifiers
+fn ident(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// quoted dot id, ports in html labels are compared after entities are decoded,
+/// so `dot_id(name)` matches port `html_escape(name)`
+fn dot_id(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\\\""))
+}
+
+impl Erd {
+    fn is_fk(&self, table: &str, column: &str) -> bool {
+        self.relations
+            .iter()
+            .any(|r| r.table == table && r.columns.iter().any(|c| c == column))
+    }
+
+    /// whether all fk columns of relation are nullable, which means optional relation
+    fn is_optional(&self, rel: &ErdRelation) -> bool {
+        self.tables
+            .iter()
+            .find(|t| t.name == rel.table)
+            .map(|t| {
+                t.columns
+                    .iter()
+                    .filter(|c| rel.columns.contains(&c.name))
+                    .all(|c| c.nullable)
+            })
+            .unwrap_or(false)
+    }
+
+    pub fn render(&self, format: ErdFormat) -> String {
+        match format {
+            ErdFormat::Mermaid => self.to_mermaid(),
+            ErdFormat::Dot => self.to_dot(),
+            ErdFormat::PlantUml => self.to_plantuml(),
+        }
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("erDiagram\n");
+        for table in self.tables.iter() {
+            writeln!(out, "    {} {{", ident(&table.name)).unwrap();
+            for col in table.columns.iter() {
+                let mut keys = vec![];
+                if col.primary {
+                    keys.push("PK");
+                }
+                if self.is_fk(&table.name, &col.name) {
+                    keys.push("FK");
+                }
+                writeln!(
+                    out,
+                    "        {} {} {}",
+                    ident(&col.data_type),
+                    ident(&col.name),
+                    keys.join(", ")
+                )
+                .unwrap();
+            }
+            out.push_str("    }\n");
+        }
+        for rel in self.relations.iter() {
+            let card = if self.is_optional(rel) { "o|" } else { "||" };
+            writeln!(
+                out,
+                "    {} }}o--{} {} : \"{}\"",
+                ident(&rel.table),
+                card,
+                ident(&rel.ref_table),
+                rel.constraint.replace('"', "'")
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph erd {\n");
+        out.push_str("    rankdir=LR;\n");
+        out.push_str("    node [shape=plaintext, fontname=\"Helvetica\"];\n");
+        for table in self.tables.iter() {
+            write!(
+                out,
+                "    {} [label=<<table border=\"0\" cellborder=\"1\" cellspacing=\"0\">\
+                 <tr><td bgcolor=\"lightgrey\"><b>{}</b></td></tr>",
+                dot_id(&table.name),
+                html_escape(&table.name)
+            )
+            .unwrap();
+            for col in table.columns.iter() {
+                let name = if col.primary {
+                    format!("<u>{}</u>", html_escape(&col.name))
+                } else {
+                    html_escape(&col.name)
+                };
+                write!(
+                    out,
+                    "<tr><td port=\"{}\" align=\"left\">{} : {}</td></tr>",
+                    html_escape(&col.name),
+                    name,
+                    html_escape(&col.data_type)
+                )
+                .unwrap();
+            }
+            out.push_str("</table>>];\n");
+        }
+        for rel in self.relations.iter() {
+            let style = if self.is_optional(rel) {
+                ", style=dashed"
+            } else {
+                ""
+            };
+            for (col, ref_col) in rel.columns.iter().zip(rel.ref_columns.iter()) {
+                writeln!(
+                    out,
+                    "    {}:{} -> {}:{} [label={}{}];",
+                    dot_id(&rel.table),
+                    dot_id(col),
+                    dot_id(&rel.ref_table),
+                    dot_id(ref_col),
+                    dot_id(&rel.constraint),
+                    style
+                )
+                .unwrap();
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_plantuml(&self) -> String {
+        let mut out = String::from("@startuml\n");
+        out.push_str("hide circle\n");
+        out.push_str("skinparam linetype ortho\n\n");
+        for table in self.tables.iter() {
+            writeln!(
+                out,
+                "entity \"{}\" as {} {{",
+                table.name,
+                ident(&table.name)
+            )
+            .unwrap();
+            let (pks, others): (Vec<&ErdColumn>, Vec<&ErdColumn>) =
+                table.columns.iter().partition(|c| c.primary);
+            for col in pks.iter() {
+                writeln!(out, "  * {} : {} <<PK>>", col.name, col.data_type).unwrap();
+            }
+            if !pks.is_empty() {
+                out.push_str("  --\n");
+            }
+            for col in others.iter() {
+                let required = if col.nullable { "" } else { "* " };
+                let fk = if self.is_fk(&table.name, &col.name) {
+                    " <<FK>>"
+                } else {
+                    ""
+                };
+                writeln!(out, "  {}{} : {}{}", required, col.name, col.data_type, fk).unwrap();
+            }
+            out.push_str("}\n\n");
+        }
+        for rel in self.relations.iter() {
+            let card = if self.is_optional(rel) { "o|" } else { "||" };
+            writeln!(
+                out,
+                "{} }}o..{} {} : {}",
+                ident(&rel.table),
+                card,
+                ident(&rel.ref_table),
+                rel.constraint
+            )
+            .unwrap();
+        }
+        out.push_str("@enduml\n");
+        out
+    }
+}
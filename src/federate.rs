@@ -0,0 +1,407 @@
+use anyhow::{anyhow, Context, Result};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use serde::{
+    ser::{SerializeMap, SerializeSeq},
+    Serialize,
+};
+use serde_json::Value;
+use sqlx::{
+    sqlite::{SqliteConnection, SqliteRow},
+    Column, Connection, Executor, Row, TypeInfo, ValueRef,
+};
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
+
+use crate::{config::Config, fl, guard, mysql::Session, output::Records, utils::read_file};
+
+/// in memory table, loaded from a source or returned by local engine
+#[derive(Debug, Clone, Default)]
+pub struct Dataset {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// a query result or local file registered as named table
+#[derive(Debug, Clone)]
+pub struct TableSource {
+    pub name: String,
+    pub source: Source,
+}
+
+#[derive(Debug, Clone)]
+pub enum Source {
+    /// query to run on a profile, `@<file>` reads SQL from file
+    Query { profile: String, sql: String },
+    /// csv, json, jsonl or parquet file, decided by extension
+    File(PathBuf),
+}
+
+impl FromStr for TableSource {
+    type Err = anyhow::Error;
+
+    /// `name=profile:sql` for query, `name=path` for file
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, rest) = s
+            .split_once('=')
+            .filter(|(name, rest)| !name.trim().is_empty() && !rest.trim().is_empty())
+            .ok_or_else(|| anyhow!(fl!("invalid-value", val = s)))?;
+        let name = name.trim().to_string();
+        let rest = rest.trim();
+        let source = match rest.split_once(':') {
+            Some((profile, sql)) if !profile.is_empty() && !profile.contains(['/', '\\', '.']) => {
+                Source::Query {
+                    profile: profile.to_string(),
+                    sql: sql.to_string(),
+                }
+            }
+            _ => Source::File(PathBuf::from(rest)),
+        };
+        Ok(TableSource { name, source })
+    }
+}
+
+struct RowMapSer<'a>(&'a [String], &'a [Value]);
+
+/// rows as maps of column name to value
+impl Serialize for Dataset {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.rows.len()))?;
+        for row in &self.rows {
+            seq.serialize_element(&RowMapSer(&self.columns, row))?;
+        }
+        seq.end()
+    }
+}
+
+impl<'a> Serialize for RowMapSer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (col, val) in self.0.iter().zip(self.1.iter()) {
+            map.serialize_entry(col, val)?;
+        }
+        map.end()
+    }
+}
+
+fn display_value(val: &Value) -> String {
+    match val {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// guess type of text value, empty text is NULL
+fn infer_value(text: &str) -> Value {
+    if text.is_empty() {
+        Value::Null
+    } else if let Ok(v) = text.parse::<i64>() {
+        Value::from(v)
+    } else if let Ok(v) = text.parse::<f64>() {
+        Value::from(v)
+    } else {
+        Value::from(text)
+    }
+}
+
+impl Dataset {
+    /// build dataset from json objects, columns are in order of first appearance
+    fn from_objects(objects: Vec<Value>) -> Result<Self> {
+        let mut columns: Vec<String> = vec![];
+        for obj in &objects {
+            let obj = obj
+                .as_object()
+                .ok_or_else(|| anyhow!(fl!("federate-json-not-object")))?;
+            for key in obj.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+        let rows = objects
+            .iter()
+            .map(|obj| {
+                columns
+                    .iter()
+                    .map(|col| obj.get(col).cloned().unwrap_or(Value::Null))
+                    .collect()
+            })
+            .collect();
+        Ok(Dataset { columns, rows })
+    }
+
+    pub fn load_file(path: &PathBuf) -> Result<Self> {
+        let path_str = path.to_string_lossy().to_string();
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" => {
+                let mut reader = csv::ReaderBuilder::new()
+                    .flexible(true)
+                    .from_path(path)
+                    .with_context(|| fl!("open-file-failed", file = path_str.clone()))?;
+                let columns: Vec<String> = reader
+                    .headers()
+                    .with_context(|| fl!("read-file-failed", file = path_str.clone()))?
+                    .iter()
+                    .map(|h| h.to_string())
+                    .collect();
+                let mut rows = vec![];
+                for record in reader.records() {
+                    let record =
+                        record.with_context(|| fl!("read-file-failed", file = path_str.clone()))?;
+                    let mut row: Vec<Value> = record.iter().map(infer_value).collect();
+                    row.resize(columns.len(), Value::Null);
+                    rows.push(row);
+                }
+                Ok(Dataset { columns, rows })
+            }
+            "json" => {
+                let content = read_file(&path_str)?;
+                let value: Value = serde_json::from_str(&content)
+                    .with_context(|| fl!("read-file-failed", file = path_str.clone()))?;
+                match value {
+                    Value::Array(objects) => Dataset::from_objects(objects),
+                    _ => Err(anyhow!(fl!("federate-json-not-object"))),
+                }
+            }
+            "jsonl" | "ndjson" => {
+                let content = read_file(&path_str)?;
+                let objects = content
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(serde_json::from_str)
+                    .collect::<Result<Vec<Value>, _>>()
+                    .with_context(|| fl!("read-file-failed", file = path_str.clone()))?;
+                Dataset::from_objects(objects)
+            }
+            "parquet" => {
+                let file = std::fs::File::open(path)
+                    .with_context(|| fl!("open-file-failed", file = path_str.clone()))?;
+                let reader = SerializedFileReader::new(file)
+                    .with_context(|| fl!("read-file-failed", file = path_str.clone()))?;
+                let columns: Vec<String> = reader
+                    .metadata()
+                    .file_metadata()
+                    .schema_descr()
+                    .root_schema()
+                    .get_fields()
+                    .iter()
+                    .map(|f| f.name().to_string())
+                    .collect();
+                let mut rows = vec![];
+                for row in reader
+                    .get_row_iter(None)
+                    .with_context(|| fl!("read-file-failed", file = path_str.clone()))?
+                {
+                    let row =
+                        row.with_context(|| fl!("read-file-failed", file = path_str.clone()))?;
+                    let mut values: HashMap<&String, Value> = HashMap::new();
+                    for (name, field) in row.get_column_iter() {
+                        values.insert(name, field.to_json_value());
+                    }
+                    rows.push(
+                        columns
+                            .iter()
+                            .map(|col| values.remove(col).unwrap_or(Value::Null))
+                            .collect(),
+                    );
+                }
+                Ok(Dataset { columns, rows })
+            }
+            _ => Err(anyhow!(fl!("federate-unknown-file", file = path_str))),
+        }
+    }
+
+    fn from_sqlite_rows(rows: Vec<SqliteRow>) -> Result<Self> {
+        let columns = match rows.first() {
+            Some(row) => row.columns().iter().map(|c| c.name().to_string()).collect(),
+            None => vec![],
+        };
+        let mut values = vec![];
+        for row in &rows {
+            let mut vals = vec![];
+            for idx in 0..row.len() {
+                let raw = row.try_get_raw(idx)?;
+                // sqlite is dynamic typed, type of value may differ from declared type
+                let val = if raw.is_null() {
+                    Value::Null
+                } else {
+                    match raw.type_info().name() {
+                        "INTEGER" | "BOOLEAN" => Value::from(row.try_get::<i64, _>(idx)?),
+                        "REAL" => Value::from(row.try_get::<f64, _>(idx)?),
+                        "BLOB" => Value::from(base64::encode(row.try_get::<Vec<u8>, _>(idx)?)),
+                        _ => Value::from(row.try_get::<String, _>(idx)?),
+                    }
+                };
+                vals.push(val);
+            }
+            values.push(vals);
+        }
+        Ok(Dataset {
+            columns,
+            rows: values,
+        })
+    }
+}
+
+impl Records for Dataset {
+    fn to_csv(&self) -> Result<String> {
+        let mut out = vec![];
+        {
+            let mut wtr = csv::Writer::from_writer(&mut out);
+            wtr.write_record(&self.columns)
+                .with_context(|| fl!("serialize-output-failed"))?;
+            for row in &self.rows {
+                wtr.write_record(row.iter().map(display_value))
+                    .with_context(|| fl!("serialize-output-failed"))?;
+            }
+        }
+        Ok(String::from_utf8_lossy(&out).to_string())
+    }
+
+    fn print_table(&self, config: &Config) {
+        if self.columns.is_empty() {
+            return;
+        }
+        let mut table = config.new_table();
+        table.set_header(&self.columns);
+        for row in &self.rows {
+            table.add_row(row.iter().map(display_value));
+        }
+        println!("{}", table);
+    }
+}
+
+fn quote_sqlite_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// in process SQLite database in memory, dropped with all registered tables
+pub struct Engine {
+    conn: SqliteConnection,
+}
+
+impl Engine {
+    pub async fn new() -> Result<Self> {
+        let conn = SqliteConnection::connect("sqlite::memory:")
+            .await
+            .with_context(|| fl!("federate-engine-failed"))?;
+        Ok(Engine { conn })
+    }
+
+    /// create table with untyped columns, so each value keeps its own type
+    pub async fn register(&mut self, name: &str, data: &Dataset) -> Result<()> {
+        if data.columns.is_empty() {
+            return Err(anyhow!(fl!("federate-empty-table", name = name)));
+        }
+        let cols = data
+            .columns
+            .iter()
+            .map(|c| quote_sqlite_ident(c))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let create = format!("CREATE TABLE {} ({})", quote_sqlite_ident(name), cols);
+        self.conn
+            .execute(create.as_str())
+            .await
+            .with_context(|| fl!("federate-register-failed", name = name))?;
+        let insert = format!(
+            "INSERT INTO {} VALUES ({})",
+            quote_sqlite_ident(name),
+            vec!["?"; data.columns.len()].join(", ")
+        );
+        let mut tx = self.conn.begin().await?;
+        for row in &data.rows {
+            let mut query = sqlx::query(&insert);
+            for val in row {
+                query = match val {
+                    Value::Null => query.bind(None::<String>),
+                    Value::Bool(b) => query.bind(*b),
+                    Value::Number(n) => match n.as_i64() {
+                        Some(i) => query.bind(i),
+                        None => query.bind(n.as_f64()),
+                    },
+                    Value::String(s) => query.bind(s.clone()),
+                    other => query.bind(other.to_string()),
+                };
+            }
+            query
+                .execute(&mut tx)
+                .await
+                .with_context(|| fl!("federate-register-failed", name = name))?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn query(&mut self, sql: &str) -> Result<Dataset> {
+        let rows = sqlx::query(sql).fetch_all(&mut self.conn).await?;
+        Dataset::from_sqlite_rows(rows)
+    }
+}
+
+async fn load_source(
+    config: &Config,
+    sessions: &mut HashMap<String, Session>,
+    source: &Source,
+) -> Result<Dataset> {
+    let (profile, sql) = match source {
+        Source::File(path) => return Dataset::load_file(path),
+        Source::Query { profile, sql } => (profile, sql),
+    };
+    if !sessions.contains_key(profile) {
+        let session = Session::connect_with(config.try_get_profile(profile)?).await?;
+        sessions.insert(profile.clone(), session);
+    }
+    let session = &sessions[profile];
+    let sql = match sql.strip_prefix('@') {
+        Some(path) => read_file(path)?,
+        None => sql.clone(),
+    };
+    guard::check(session, &sql, false).await?;
+    let output = session.query(&sql).await?;
+    let columns = if output.rows.is_empty() {
+        // keep columns of empty result, so that join on it still works
+        session
+            .column_types(&sql)
+            .await?
+            .into_iter()
+            .map(|c| c.name)
+            .collect()
+    } else {
+        output.header()
+    };
+    Ok(Dataset {
+        columns,
+        rows: output.json_values()?,
+    })
+}
+
+/// load every source into a new engine, sources of same profile share one session
+pub async fn load(config: &Config, sources: &[TableSource]) -> Result<Engine> {
+    let mut engine = Engine::new().await?;
+    let mut sessions: HashMap<String, Session> = HashMap::new();
+    let mut result = Ok(());
+    for table in sources {
+        result = match load_source(config, &mut sessions, &table.source).await {
+            Ok(data) => engine.register(&table.name, &data).await,
+            Err(e) => Err(e.context(fl!("federate-register-failed", name = table.name.as_str()))),
+        };
+        if result.is_err() {
+            break;
+        }
+    }
+    for session in sessions.values() {
+        session.close().await;
+    }
+    result.map(|_| engine)
+}
@@ -0,0 +1,475 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use sqlparser::{
+    ast::{BinaryOperator, Expr, Query, Select, SetExpr, Statement, TableFactor, TableWithJoins},
+    dialect::{keywords::ALL_KEYWORDS, MySqlDialect},
+    parser::Parser,
+    tokenizer::{Token, Tokenizer, Whitespace},
+};
+use std::str::FromStr;
+
+use crate::{fl, utils::split_sql_chunks};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum KeywordCase {
+    #[default]
+    #[serde(rename = "upper")]
+    Upper,
+    #[serde(rename = "lower")]
+    Lower,
+}
+
+impl FromStr for KeywordCase {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let val = match &*s.to_ascii_lowercase() {
+            "upper" => KeywordCase::Upper,
+            "lower" => KeywordCase::Lower,
+            _ => return Err(anyhow!(fl!("invalid-value", val = s))),
+        };
+        Ok(val)
+    }
+}
+
+impl std::fmt::Display for KeywordCase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let val = match self {
+            KeywordCase::Upper => "upper",
+            KeywordCase::Lower => "lower",
+        };
+        write!(f, "{}", val)
+    }
+}
+
+fn default_indent() -> usize {
+    2
+}
+
+fn default_width() -> usize {
+    80
+}
+
+/// settings of `dcli fmt` and `%fmt`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatConfig {
+    #[serde(default)]
+    pub keyword_case: KeywordCase,
+    #[serde(default = "default_indent")]
+    pub indent: usize,
+    /// clauses longer than this are broken into one item per line
+    #[serde(default = "default_width")]
+    pub width: usize,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self {
+            keyword_case: KeywordCase::default(),
+            indent: default_indent(),
+            width: default_width(),
+        }
+    }
+}
+
+struct Printer<'a> {
+    config: &'a FormatConfig,
+}
+
+impl<'a> Printer<'a> {
+    fn pad(&self, level: usize) -> String {
+        " ".repeat(level * self.config.indent)
+    }
+
+    /// `head a, b` if it fits in width, otherwise one item per line
+    fn list(&self, head: &str, items: &[String], level: usize) -> String {
+        let line = format!("{}{} {}", self.pad(level), head, items.join(", "));
+        if line.len() <= self.config.width {
+            return line;
+        }
+        let items: Vec<String> = items
+            .iter()
+            .map(|item| format!("{}{}", self.pad(level + 1), item))
+            .collect();
+        format!("{}{}\n{}", self.pad(level), head, items.join(",\n"))
+    }
+
+    /// `head expr` if it fits in width, otherwise top level AND or OR operands on their own lines
+    fn condition(&self, head: &str, expr: &Expr, level: usize) -> String {
+        let line = format!("{}{} {}", self.pad(level), head, expr);
+        if line.len() <= self.config.width {
+            return line;
+        }
+        let op = match expr {
+            Expr::BinaryOp {
+                op: BinaryOperator::Or,
+                ..
+            } => BinaryOperator::Or,
+            _ => BinaryOperator::And,
+        };
+        let mut operands = vec![];
+        flatten(expr, &op, &mut operands);
+        let mut out = format!("{}{} {}", self.pad(level), head, operands[0]);
+        for operand in &operands[1..] {
+            out.push_str(&format!("\n{}{} {}", self.pad(level + 1), op, operand));
+        }
+        out
+    }
+
+    fn query(&self, query: &Query, level: usize) -> String {
+        let mut lines = vec![];
+        if !query.ctes.is_empty() {
+            let ctes: Vec<String> = query
+                .ctes
+                .iter()
+                .map(|cte| {
+                    format!(
+                        "{} AS (\n{}\n{})",
+                        cte.alias,
+                        self.query(&cte.query, level + 1),
+                        self.pad(level)
+                    )
+                })
+                .collect();
+            lines.push(format!("{}WITH {}", self.pad(level), ctes.join(", ")));
+        }
+        lines.push(self.set_expr(&query.body, level));
+        if !query.order_by.is_empty() {
+            let items: Vec<String> = query.order_by.iter().map(|o| o.to_string()).collect();
+            lines.push(self.list("ORDER BY", &items, level));
+        }
+        if let Some(limit) = &query.limit {
+            lines.push(format!("{}LIMIT {}", self.pad(level), limit));
+        }
+        if let Some(offset) = &query.offset {
+            lines.push(format!("{}{}", self.pad(level), offset));
+        }
+        if let Some(fetch) = &query.fetch {
+            lines.push(format!("{}{}", self.pad(level), fetch));
+        }
+        lines.join("\n")
+    }
+
+    fn set_expr(&self, expr: &SetExpr, level: usize) -> String {
+        match expr {
+            SetExpr::Select(select) => self.select(select, level),
+            SetExpr::Query(query) => format!(
+                "{}(\n{}\n{})",
+                self.pad(level),
+                self.query(query, level + 1),
+                self.pad(level)
+            ),
+            SetExpr::SetOperation {
+                op,
+                all,
+                left,
+                right,
+            } => format!(
+                "{}\n{}{}{}\n{}",
+                self.set_expr(left, level),
+                self.pad(level),
+                op,
+                if *all { " ALL" } else { "" },
+                self.set_expr(right, level)
+            ),
+            SetExpr::Values(values) => {
+                let rows: Vec<String> = values
+                    .0
+                    .iter()
+                    .map(|row| {
+                        let row: Vec<String> = row.iter().map(|v| v.to_string()).collect();
+                        format!("({})", row.join(", "))
+                    })
+                    .collect();
+                self.list("VALUES", &rows, level)
+            }
+        }
+    }
+
+    fn select(&self, select: &Select, level: usize) -> String {
+        let mut lines = vec![];
+        let head = if select.distinct {
+            "SELECT DISTINCT"
+        } else {
+            "SELECT"
+        };
+        let items: Vec<String> = select.projection.iter().map(|p| p.to_string()).collect();
+        lines.push(self.list(head, &items, level));
+        if !select.from.is_empty() {
+            let tables: Vec<String> = select
+                .from
+                .iter()
+                .map(|t| self.table_with_joins(t, level))
+                .collect();
+            if tables.iter().any(|t| t.contains('\n')) {
+                lines.push(format!(
+                    "{}FROM {}",
+                    self.pad(level),
+                    tables.join(&format!(",\n{}", self.pad(level + 1)))
+                ));
+            } else {
+                lines.push(self.list("FROM", &tables, level));
+            }
+        }
+        if let Some(selection) = &select.selection {
+            lines.push(self.condition("WHERE", selection, level));
+        }
+        if !select.group_by.is_empty() {
+            let items: Vec<String> = select.group_by.iter().map(|g| g.to_string()).collect();
+            lines.push(self.list("GROUP BY", &items, level));
+        }
+        if let Some(having) = &select.having {
+            lines.push(self.condition("HAVING", having, level));
+        }
+        lines.join("\n")
+    }
+
+    fn table_with_joins(&self, table: &TableWithJoins, level: usize) -> String {
+        let mut out = self.table_factor(&table.relation, level);
+        for join in &table.joins {
+            out.push_str(&format!(
+                "\n{}{}",
+                self.pad(level + 1),
+                join.to_string().trim_start()
+            ));
+        }
+        out
+    }
+
+    fn table_factor(&self, factor: &TableFactor, level: usize) -> String {
+        match factor {
+            TableFactor::Derived {
+                lateral,
+                subquery,
+                alias,
+            } => {
+                let mut out = format!(
+                    "{}(\n{}\n{})",
+                    if *lateral { "LATERAL " } else { "" },
+                    self.query(subquery, level + 1),
+                    self.pad(level)
+                );
+                if let Some(alias) = alias {
+                    out.push_str(&format!(" AS {}", alias));
+                }
+                out
+            }
+            _ => factor.to_string(),
+        }
+    }
+
+    fn statement(&self, statement: &Statement) -> String {
+        match statement {
+            Statement::Query(query) => self.query(query, 0),
+            Statement::Insert {
+                table_name,
+                columns,
+                source,
+            } => {
+                let mut head = format!("INSERT INTO {}", table_name);
+                if !columns.is_empty() {
+                    let columns: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+                    head.push_str(&format!(" ({})", columns.join(", ")));
+                }
+                format!("{}\n{}", head, self.query(source, 0))
+            }
+            Statement::Update {
+                table_name,
+                assignments,
+                selection,
+            } => {
+                let items: Vec<String> = assignments.iter().map(|a| a.to_string()).collect();
+                let mut lines = vec![
+                    format!("UPDATE {}", table_name),
+                    self.list("SET", &items, 0),
+                ];
+                if let Some(selection) = selection {
+                    lines.push(self.condition("WHERE", selection, 0));
+                }
+                lines.join("\n")
+            }
+            Statement::Delete {
+                table_name,
+                selection,
+            } => {
+                let mut lines = vec![format!("DELETE FROM {}", table_name)];
+                if let Some(selection) = selection {
+                    lines.push(self.condition("WHERE", selection, 0));
+                }
+                lines.join("\n")
+            }
+            _ => statement.to_string(),
+        }
+    }
+}
+
+/// operands of nested `op` expressions, such as `a AND b AND c`
+fn flatten<'e>(expr: &'e Expr, op: &BinaryOperator, out: &mut Vec<&'e Expr>) {
+    match expr {
+        Expr::BinaryOp {
+            left,
+            op: inner,
+            right,
+        } if inner == op => {
+            flatten(left, op, out);
+            flatten(right, op, out);
+        }
+        _ => out.push(expr),
+    }
+}
+
+/// lower case keywords printed by formatter, which are always in upper case,
+/// quoted text and mixed case identifiers are kept
+fn lower_keywords(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\'' || c == '"' || c == '`' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+            out.extend(&chars[start..i]);
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let upper = word.chars().all(|c| !c.is_ascii_lowercase());
+            if upper && ALL_KEYWORDS.binary_search(&word.as_str()).is_ok() {
+                out.push_str(&word.to_ascii_lowercase());
+            } else {
+                out.push_str(&word);
+            }
+        } else {
+            out.push(c);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// comments of statement, which are moved above the formatted statement
+fn comments(sql: &str) -> Vec<String> {
+    let dialect = MySqlDialect {};
+    Tokenizer::new(&dialect, sql)
+        .tokenize()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Whitespace(Whitespace::SingleLineComment(c)) => {
+                Some(format!("--{}", c.trim_end()))
+            }
+            Token::Whitespace(Whitespace::MultiLineComment(c)) => Some(format!("/*{}*/", c)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// pretty print one statement, it's kept as is if it can't be parsed,
+/// none if the formatted text doesn't parse to the same statement
+fn format_statement(sql: &str, config: &FormatConfig) -> Option<String> {
+    let dialect = MySqlDialect {};
+    let original = sql.trim().trim_end_matches(';').trim_end().to_string();
+    // sqlparser doesn't handle backslash escapes of MySQL strings
+    if original.contains('\\') {
+        return Some(original);
+    }
+    let statements = match Parser::parse_sql(&dialect, &original) {
+        Ok(statements) if !statements.is_empty() => statements,
+        _ => return Some(original),
+    };
+    let printer = Printer { config };
+    let formatted: Vec<String> = statements
+        .iter()
+        .map(|s| match config.keyword_case {
+            KeywordCase::Upper => printer.statement(s),
+            KeywordCase::Lower => lower_keywords(&printer.statement(s)),
+        })
+        .collect();
+    let formatted = formatted.join(";\n");
+    match Parser::parse_sql(&dialect, &formatted) {
+        Ok(reparsed) if reparsed == statements => {}
+        _ => {
+            log::warn!("formatted statement differs from original: {}", original);
+            return None;
+        }
+    }
+    let mut lines = comments(sql);
+    lines.push(formatted);
+    Some(lines.join("\n"))
+}
+
+/// pretty print statements separated by blank lines, each one ends with `;`,
+/// comments between statements are kept, the text is returned unchanged
+/// if any statement can't be formatted without changing it
+pub fn format_sql(sql: &str, config: &FormatConfig) -> String {
+    let mut statements = vec![];
+    for chunk in split_sql_chunks(sql) {
+        let formatted = if chunk.has_code {
+            match format_statement(&chunk.text, config) {
+                Some(formatted) => formatted,
+                None => return sql.to_string(),
+            }
+        } else {
+            chunk.text
+        };
+        // statement kept as is may end with a line comment
+        let last = formatted.lines().last().unwrap_or_default();
+        if !chunk.has_code && !chunk.terminated {
+            statements.push(formatted);
+        } else if last.contains("--") || last.contains('#') {
+            statements.push(format!("{}\n;", formatted));
+        } else {
+            statements.push(format!("{};", formatted));
+        }
+    }
+    statements.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keep_comments_of_dump() {
+        let sql = "-- MySQL dump 10.13\n\
+                   --\n\
+                   -- Host: localhost    Database: shop\n\
+                   /*!40101 SET @OLD_CHARACTER_SET_CLIENT=@@CHARACTER_SET_CLIENT */;\n\
+                   /*!40101 SET NAMES utf8mb4 */;\n\
+                   select a from t; -- trailing note\n\
+                   select b from s where id = 1;\n\
+                   -- end of file\n";
+        let formatted = format_sql(sql, &FormatConfig::default());
+        for kept in [
+            "-- MySQL dump 10.13",
+            "-- Host: localhost    Database: shop",
+            "/*!40101 SET @OLD_CHARACTER_SET_CLIENT=@@CHARACTER_SET_CLIENT */;",
+            "/*!40101 SET NAMES utf8mb4 */;",
+            "-- trailing note",
+            "-- end of file",
+        ] {
+            assert!(
+                formatted.contains(kept),
+                "{} missing in:\n{}",
+                kept,
+                formatted
+            );
+        }
+        assert!(formatted.contains("SELECT a\nFROM t;"), "{}", formatted);
+        assert!(formatted.ends_with("-- end of file"), "{}", formatted);
+        assert_eq!(format_sql(&formatted, &FormatConfig::default()), formatted);
+    }
+
+    #[test]
+    fn keep_text_after_last_semicolon() {
+        let formatted = format_sql("select 1; # done", &FormatConfig::default());
+        assert_eq!(formatted, "SELECT 1;\n\n# done");
+    }
+}
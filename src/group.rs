@@ -0,0 +1,169 @@
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use comfy_table::{Cell, Color};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+use crate::{
+    config::{Config, Profile},
+    fl, guard,
+    mysql::Session,
+    output::QueryOutput,
+};
+
+/// outputs of every statement on one profile
+struct ProfileOutputs {
+    profile: String,
+    outputs: Result<Vec<QueryOutput>>,
+}
+
+async fn run_on(profile: &Profile, statements: &[String]) -> Result<Vec<QueryOutput>> {
+    let session = Session::connect_with(profile).await?;
+    let mut outputs = vec![];
+    for sql in statements {
+        match session.query(sql).await {
+            Ok(output) => outputs.push(output),
+            Err(e) => {
+                session.close().await;
+                return Err(e);
+            }
+        }
+    }
+    session.close().await;
+    Ok(outputs)
+}
+
+/// print output of one statement from all profiles in one table with leading `profile` column,
+/// outputs whose columns differ from the first one are printed separately
+fn print_merged(config: &Config, outputs: &[(&str, &QueryOutput)]) {
+    let header = match outputs
+        .iter()
+        .map(|(_, o)| o.header())
+        .find(|h| !h.is_empty())
+    {
+        Some(header) => header,
+        None => return,
+    };
+    let mut table = config.new_table();
+    let mut cols = vec!["profile".to_string()];
+    cols.extend(header.iter().cloned());
+    table.set_header(cols);
+    let mut mismatched = vec![];
+    for (profile, output) in outputs {
+        let this = output.header();
+        if this.is_empty() {
+            continue;
+        }
+        if this != header {
+            mismatched.push((*profile, *output));
+            continue;
+        }
+        for row in output.values() {
+            let mut cells = vec![Cell::new(profile)];
+            cells.extend(row.into_iter().map(Cell::new));
+            table.add_row(cells);
+        }
+    }
+    println!("{}", table);
+    for (profile, output) in mismatched {
+        println!("{}", fl!("group-columns-differ", profile = profile));
+        output.to_print_table(config, false);
+    }
+}
+
+/// run statements on every profile concurrently, at most `parallel` profiles at a time,
+/// failure of one profile does not stop the others and is reported after all results
+pub async fn exec(
+    config: &Config,
+    profiles: Vec<Profile>,
+    statements: Vec<String>,
+    parallel: usize,
+    split: bool,
+    vertical: bool,
+    force: bool,
+) -> Result<()> {
+    // check safety modes before running anything, confirm writes once for all profiles
+    let mut to_confirm = vec![];
+    for profile in &profiles {
+        let mode = profile.mode.unwrap_or_default();
+        for sql in &statements {
+            if guard::precheck(mode, sql, force).with_context(|| format!("[{}]", profile.name))?
+                && !to_confirm.contains(&profile.name)
+            {
+                to_confirm.push(profile.name.clone());
+            }
+        }
+    }
+    if !to_confirm.is_empty()
+        && !guard::confirm(&fl!(
+            "guard-group-confirm",
+            profiles = to_confirm.join(", ")
+        ))?
+    {
+        return Err(anyhow!(fl!("guard-cancelled")));
+    }
+    let semaphore = Arc::new(Semaphore::new(parallel.max(1)));
+    let statements = Arc::new(statements);
+    let total = profiles.len();
+    let mut handles = vec![];
+    for profile in profiles {
+        let semaphore = semaphore.clone();
+        let statements = statements.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire().await;
+            let outputs = run_on(&profile, &statements).await;
+            ProfileOutputs {
+                profile: profile.name,
+                outputs,
+            }
+        }));
+    }
+    let mut results = vec![];
+    for handle in handles {
+        results.push(handle.await?);
+    }
+
+    if split || vertical {
+        for result in &results {
+            if let Ok(outputs) = &result.outputs {
+                println!("{}", format!("[{}]", result.profile).bold());
+                for output in outputs {
+                    output.to_print_table(config, vertical);
+                }
+            }
+        }
+    } else {
+        for idx in 0..statements.len() {
+            let outputs: Vec<(&str, &QueryOutput)> = results
+                .iter()
+                .filter_map(|r| match &r.outputs {
+                    Ok(outputs) => outputs.get(idx).map(|o| (r.profile.as_str(), o)),
+                    Err(_) => None,
+                })
+                .collect();
+            print_merged(config, &outputs);
+        }
+    }
+
+    let failed: Vec<(&str, &anyhow::Error)> = results
+        .iter()
+        .filter_map(|r| r.outputs.as_ref().err().map(|e| (r.profile.as_str(), e)))
+        .collect();
+    if failed.is_empty() {
+        return Ok(());
+    }
+    let mut table = config.new_table();
+    table.set_header(vec!["profile", "error"]);
+    for (profile, e) in &failed {
+        table.add_row(vec![
+            Cell::new(profile),
+            Cell::new(format!("{:#}", e)).fg(Color::Red),
+        ]);
+    }
+    println!("{}", table);
+    Err(anyhow!(fl!(
+        "group-failed",
+        failed = failed.len().to_string(),
+        total = total.to_string()
+    )))
+}
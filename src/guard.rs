@@ -0,0 +1,292 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use sqlparser::{
+    ast::Statement,
+    dialect::MySqlDialect,
+    parser::Parser,
+    tokenizer::{Token, Tokenizer},
+};
+use std::io::Write;
+
+use crate::{config::SafetyMode, fl, mysql::Session, utils::split_sql};
+
+/// whether a statement may change data or schema
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// unquoted words of statement in upper case, comments and literals are skipped,
+/// words inside parentheses are skipped too if `top_level` is set
+fn words(sql: &str, top_level: bool) -> Vec<String> {
+    let dialect = MySqlDialect {};
+    match Tokenizer::new(&dialect, sql).tokenize() {
+        Ok(tokens) => {
+            let mut depth = 0usize;
+            let mut words = vec![];
+            for token in tokens {
+                match token {
+                    Token::LParen => depth += 1,
+                    Token::RParen => depth = depth.saturating_sub(1),
+                    Token::Word(word)
+                        if word.quote_style.is_none() && (!top_level || depth == 0) =>
+                    {
+                        words.push(word.value.to_ascii_uppercase());
+                    }
+                    _ => {}
+                }
+            }
+            words
+        }
+        Err(_) => sql
+            .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_ascii_uppercase())
+            .collect(),
+    }
+}
+
+/// words of statement wrapped by `EXPLAIN ANALYZE [FORMAT = TREE]`, which runs it
+fn analyzed(words: &[String]) -> Option<&[String]> {
+    match words {
+        [explain, analyze, rest @ ..]
+            if matches!(explain.as_str(), "EXPLAIN" | "DESC" | "DESCRIBE")
+                && analyze == "ANALYZE" =>
+        {
+            match rest {
+                [format, _, rest @ ..] if format == "FORMAT" => Some(rest),
+                _ => Some(rest),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// statements sqlparser can't parse are classified by leading keyword,
+/// unknown ones are treated as writes
+fn classify_words(words: &[String]) -> Access {
+    if let Some(inner) = analyzed(words) {
+        return classify_words(inner);
+    }
+    let has = |kw: &str| words.iter().any(|w| w == kw);
+    match words.first().map(String::as_str) {
+        Some("SELECT") | Some("TABLE") | Some("VALUES") => {
+            if has("OUTFILE") || has("DUMPFILE") {
+                Access::Write
+            } else {
+                Access::Read
+            }
+        }
+        Some("WITH") => {
+            if has("INSERT") || has("UPDATE") || has("DELETE") || has("OUTFILE") {
+                Access::Write
+            } else {
+                Access::Read
+            }
+        }
+        Some("SHOW") | Some("DESC") | Some("DESCRIBE") | Some("EXPLAIN") | Some("USE")
+        | Some("HELP") | Some("BEGIN") | Some("COMMIT") | Some("ROLLBACK") => Access::Read,
+        // START SLAVE, START REPLICA and the like change the server
+        Some("START") => match words.get(1).map(String::as_str) {
+            Some("TRANSACTION") => Access::Read,
+            _ => Access::Write,
+        },
+        Some("SET") => {
+            // turning off read only transactions of a read-only session is a write too
+            let read_write = words
+                .windows(2)
+                .any(|pair| pair[0] == "READ" && pair[1] == "WRITE");
+            if has("GLOBAL")
+                || has("PERSIST")
+                || has("PERSIST_ONLY")
+                || has("PASSWORD")
+                || (has("TRANSACTION") && read_write)
+                || has("TRANSACTION_READ_ONLY")
+                || has("TX_READ_ONLY")
+            {
+                Access::Write
+            } else {
+                Access::Read
+            }
+        }
+        _ => Access::Write,
+    }
+}
+
+pub fn classify(sql: &str) -> Access {
+    let words = words(sql, false);
+    let dialect = MySqlDialect {};
+    let statements = match Parser::parse_sql(&dialect, sql) {
+        Ok(statements) if statements.len() == 1 => statements,
+        _ => return classify_words(&words),
+    };
+    match &statements[0] {
+        Statement::Query(_) => classify_words(&words),
+        Statement::ShowVariable { .. }
+        | Statement::ShowColumns { .. }
+        | Statement::StartTransaction { .. }
+        | Statement::Commit { .. }
+        | Statement::Rollback { .. } => Access::Read,
+        Statement::SetVariable { .. } | Statement::SetTransaction { .. } => classify_words(&words),
+        _ => Access::Write,
+    }
+}
+
+/// UPDATE or DELETE without WHERE or LIMIT, which touches every row
+pub fn is_unbounded(sql: &str) -> bool {
+    let dialect = MySqlDialect {};
+    if let Ok(statements) = Parser::parse_sql(&dialect, sql) {
+        if let [Statement::Update { selection, .. }] | [Statement::Delete { selection, .. }] =
+            statements.as_slice()
+        {
+            return selection.is_none();
+        }
+    }
+    // sqlparser doesn't support LIMIT and multiple tables in UPDATE and DELETE
+    let words = words(sql, true);
+    let words = analyzed(&words).unwrap_or(&words);
+    matches!(
+        words.first().map(String::as_str),
+        Some("UPDATE") | Some("DELETE")
+    ) && !words.iter().any(|w| w == "WHERE" || w == "LIMIT")
+}
+
+/// check statements against safety mode without a connection, UPDATE and DELETE
+/// without WHERE or LIMIT are refused in every mode unless forced,
+/// return whether they still need to be confirmed
+pub fn precheck(mode: SafetyMode, sql: &str, force: bool) -> Result<bool> {
+    let mut need_confirm = false;
+    for statement in split_sql(sql) {
+        if classify(&statement) == Access::Read {
+            continue;
+        }
+        if mode == SafetyMode::ReadOnly {
+            return Err(anyhow!(fl!("guard-read-only", sql = statement.trim())));
+        }
+        if force {
+            continue;
+        }
+        if is_unbounded(&statement) {
+            return Err(anyhow!(fl!("guard-unbounded", sql = statement.trim())));
+        }
+        need_confirm |= mode == SafetyMode::ConfirmWrites;
+    }
+    Ok(need_confirm)
+}
+
+/// ask user to continue on terminal, anything but y or yes is a no
+pub fn confirm(message: &str) -> Result<bool> {
+    print!("{} [y/N] ", message.color(Color::Yellow));
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim().to_ascii_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+/// refuse or confirm statement according to safety mode of session's profile,
+/// estimated affected rows from EXPLAIN are shown when asking
+pub async fn check(session: &Session, sql: &str, force: bool) -> Result<()> {
+    if !precheck(session.mode(), sql, force)? {
+        return Ok(());
+    }
+    let rows = match session.estimate_rows(sql).await {
+        Ok(Some(rows)) => rows.to_string(),
+        _ => "?".to_string(),
+    };
+    println!("{}", sql.trim());
+    if confirm(&fl!("guard-confirm", rows = rows))? {
+        Ok(())
+    } else {
+        Err(anyhow!(fl!("guard-cancelled")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_statements() {
+        assert_eq!(classify("select * from t"), Access::Read);
+        assert_eq!(
+            classify("select * from t into outfile '/tmp/t.csv'"),
+            Access::Write
+        );
+        assert_eq!(classify("select 1 into dumpfile '/tmp/t'"), Access::Write);
+        assert_eq!(classify("set names utf8mb4"), Access::Read);
+        assert_eq!(classify("set global max_connections = 10"), Access::Write);
+        assert_eq!(classify("start transaction"), Access::Read);
+        assert_eq!(classify("start transaction read only"), Access::Read);
+        assert_eq!(classify("start slave"), Access::Write);
+        assert_eq!(classify("start replica"), Access::Write);
+        assert_eq!(classify("start group_replication"), Access::Write);
+        assert_eq!(classify("set session transaction read only"), Access::Read);
+        assert_eq!(
+            classify("set session transaction read write"),
+            Access::Write
+        );
+        assert_eq!(classify("set transaction read write"), Access::Write);
+        assert_eq!(
+            classify("set transaction isolation level read committed, read write"),
+            Access::Write
+        );
+        assert_eq!(classify("set transaction_read_only = 0"), Access::Write);
+        assert_eq!(classify("set @@session.tx_read_only = off"), Access::Write);
+        assert_eq!(
+            classify("delete t1, t2 from t1 join t2 on t1.id = t2.id"),
+            Access::Write
+        );
+        assert_eq!(classify("call cleanup()"), Access::Write);
+        assert_eq!(classify("explain select * from t"), Access::Read);
+        assert_eq!(classify("explain analyze select * from t"), Access::Read);
+        assert_eq!(
+            classify("explain analyze format = tree update t1 join t2 set t1.a = 1"),
+            Access::Write
+        );
+    }
+
+    #[test]
+    fn unbounded_statements() {
+        assert!(is_unbounded("delete from t"));
+        assert!(!is_unbounded("delete from t limit 10"));
+        assert!(!is_unbounded("delete from t where id = 1"));
+        assert!(is_unbounded(
+            "delete t1, t2 from t1 join t2 on t1.id = t2.id"
+        ));
+        assert!(!is_unbounded(
+            "delete t1 from t1 join t2 on t1.id = t2.id where t2.a = 1"
+        ));
+        assert!(!is_unbounded(
+            "update t set a = 1 where id in (select id from s)"
+        ));
+        assert!(is_unbounded(
+            "update t set a = (select max(a) from s where s.id = 1)"
+        ));
+        assert!(is_unbounded(
+            "explain analyze update t1 join t2 set t1.a = 1"
+        ));
+        assert!(!is_unbounded("select * from t"));
+    }
+
+    #[test]
+    fn precheck_modes() {
+        let update = "update t set a = 1";
+        assert!(precheck(SafetyMode::ReadOnly, "select 1", false).is_ok());
+        assert!(precheck(SafetyMode::ReadOnly, update, true).is_err());
+        assert!(precheck(SafetyMode::ReadOnly, "select 1; call p()", false).is_err());
+        assert!(precheck(SafetyMode::ConfirmWrites, update, false).is_err());
+        assert!(!precheck(SafetyMode::ConfirmWrites, "select 1", false).unwrap());
+        assert!(precheck(SafetyMode::ConfirmWrites, "delete from t limit 1", false).unwrap());
+        assert!(!precheck(SafetyMode::ConfirmWrites, update, true).unwrap());
+        assert!(precheck(SafetyMode::Unrestricted, update, false).is_err());
+        assert!(!precheck(SafetyMode::Unrestricted, update, true).unwrap());
+        assert!(!precheck(
+            SafetyMode::Unrestricted,
+            "delete from t where id = 1",
+            false
+        )
+        .unwrap());
+    }
+}
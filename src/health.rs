@@ -0,0 +1,411 @@
+use anyhow::{Context, Result};
+use comfy_table::{Cell, Color};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::{config::Config, fl, mysql::Session, output::Records};
+
+/// buffer pool hit ratio below this is a warning
+const HIT_RATIO_WARN: f64 = 0.99;
+/// buffer pool hit ratio below this is critical
+const HIT_RATIO_CRIT: f64 = 0.95;
+/// replication lag seconds above this is a warning
+const LAG_WARN: u64 = 60;
+/// replication lag seconds above this is critical
+const LAG_CRIT: u64 = 600;
+/// tables with free space ratio above this are reported as fragmented
+const FRAGMENT_RATIO: f64 = 0.2;
+/// tables with less free space than this are never reported as fragmented
+const FRAGMENT_MIN_BYTES: u64 = 64 * 1024 * 1024;
+/// connection usage above this is a warning
+const CONN_USAGE_WARN: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Severity {
+    #[serde(rename = "ok")]
+    Ok,
+    #[serde(rename = "info")]
+    Info,
+    #[serde(rename = "warn")]
+    Warn,
+    #[serde(rename = "crit")]
+    Crit,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let val = match self {
+            Severity::Ok => "ok",
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Crit => "crit",
+        };
+        write!(f, "{}", val)
+    }
+}
+
+/// one check of health report, `section` and `name` are stable keys for monitoring
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthItem {
+    pub section: String,
+    pub name: String,
+    pub value: String,
+    pub severity: Severity,
+    pub note: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HealthReport {
+    pub items: Vec<HealthItem>,
+}
+
+impl HealthReport {
+    fn add(&mut self, section: &str, name: &str, value: impl ToString, severity: Severity) {
+        self.add_with_note(section, name, value, severity, String::new());
+    }
+
+    fn add_with_note(
+        &mut self,
+        section: &str,
+        name: &str,
+        value: impl ToString,
+        severity: Severity,
+        note: String,
+    ) {
+        self.items.push(HealthItem {
+            section: section.to_string(),
+            name: name.to_string(),
+            value: value.to_string(),
+            severity,
+            note,
+        });
+    }
+
+    /// record a check which can not be run, such as sys schema is missing
+    fn unavailable(&mut self, section: &str, name: &str, err: anyhow::Error) {
+        self.add_with_note(
+            section,
+            name,
+            "-",
+            Severity::Info,
+            fl!("health-unavailable", error = format!("{:#}", err)),
+        );
+    }
+}
+
+impl Records for HealthReport {
+    fn to_csv(&self) -> Result<String> {
+        let mut out = vec![];
+        {
+            let mut wtr = csv::Writer::from_writer(&mut out);
+            for item in &self.items {
+                wtr.serialize(item)
+                    .with_context(|| fl!("serialize-output-failed"))?;
+            }
+        }
+        Ok(String::from_utf8_lossy(&out).to_string())
+    }
+
+    fn print_table(&self, config: &Config) {
+        let mut table = config.new_table();
+        table.set_header(vec!["section", "name", "value", "severity", "note"]);
+        for item in &self.items {
+            let severity = Cell::new(item.severity);
+            let severity = match item.severity {
+                Severity::Ok => severity.fg(Color::Green),
+                Severity::Info => severity,
+                Severity::Warn => severity.fg(Color::Yellow),
+                Severity::Crit => severity.fg(Color::Red),
+            };
+            table.add_row(vec![
+                Cell::new(&item.section),
+                Cell::new(&item.name),
+                Cell::new(&item.value),
+                severity,
+                Cell::new(&item.note),
+            ]);
+        }
+        println!("{}", table);
+    }
+}
+
+fn format_uptime(secs: u64) -> String {
+    format!(
+        "{}d {}h {}m",
+        secs / 86400,
+        secs % 86400 / 3600,
+        secs % 3600 / 60
+    )
+}
+
+fn format_bytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / 1048576.0)
+}
+
+fn get_num(map: &HashMap<String, String>, name: &str) -> Option<f64> {
+    map.get(name).and_then(|v| v.parse::<f64>().ok())
+}
+
+/// compare variable value case insensitively, values such as `ON` and `on` are both used
+fn var_is(vars: &HashMap<String, String>, name: &str, expected: &str) -> bool {
+    vars.get(name)
+        .map(|v| v.eq_ignore_ascii_case(expected))
+        .unwrap_or(false)
+}
+
+fn check_replication(report: &mut HealthReport, status: Option<HashMap<String, String>>) {
+    let status = match status {
+        Some(status) => status,
+        None => {
+            report.add_with_note(
+                "replication",
+                "role",
+                "primary",
+                Severity::Info,
+                fl!("health-not-replica"),
+            );
+            return;
+        }
+    };
+    // column names changed from Slave/Master to Replica/Source in 8.0.22
+    let get = |new: &str, old: &str| {
+        status
+            .get(new)
+            .or_else(|| status.get(old))
+            .cloned()
+            .unwrap_or_default()
+    };
+    report.add("replication", "role", "replica", Severity::Info);
+    for (name, new, old) in [
+        ("io_running", "Replica_IO_Running", "Slave_IO_Running"),
+        ("sql_running", "Replica_SQL_Running", "Slave_SQL_Running"),
+    ] {
+        let val = get(new, old);
+        let severity = if val.eq_ignore_ascii_case("yes") {
+            Severity::Ok
+        } else {
+            Severity::Crit
+        };
+        report.add("replication", name, val, severity);
+    }
+    let lag = get("Seconds_Behind_Source", "Seconds_Behind_Master");
+    match lag.parse::<u64>() {
+        Ok(secs) => {
+            let severity = if secs > LAG_CRIT {
+                Severity::Crit
+            } else if secs > LAG_WARN {
+                Severity::Warn
+            } else {
+                Severity::Ok
+            };
+            report.add("replication", "lag_seconds", secs, severity);
+        }
+        // NULL lag means replication is broken or not started
+        Err(_) => report.add("replication", "lag_seconds", "NULL", Severity::Crit),
+    }
+    let error = get("Last_Error", "Last_Error");
+    if !error.is_empty() {
+        report.add("replication", "last_error", error, Severity::Crit);
+    }
+}
+
+fn check_variables(
+    report: &mut HealthReport,
+    status: &HashMap<String, String>,
+    vars: &HashMap<String, String>,
+) {
+    let expect = |ok: bool| if ok { Severity::Ok } else { Severity::Warn };
+    let get = |name: &str| vars.get(name).cloned().unwrap_or_default();
+
+    let flush = get("innodb_flush_log_at_trx_commit");
+    report.add_with_note(
+        "variables",
+        "innodb_flush_log_at_trx_commit",
+        &flush,
+        expect(flush == "1"),
+        fl!("health-flush-log"),
+    );
+    let log_bin = var_is(vars, "log_bin", "ON");
+    report.add("variables", "log_bin", get("log_bin"), expect(log_bin));
+    if log_bin {
+        let sync = get("sync_binlog");
+        report.add_with_note(
+            "variables",
+            "sync_binlog",
+            &sync,
+            expect(sync == "1"),
+            fl!("health-sync-binlog"),
+        );
+    }
+    report.add(
+        "variables",
+        "slow_query_log",
+        get("slow_query_log"),
+        expect(var_is(vars, "slow_query_log", "ON")),
+    );
+    let sql_mode = get("sql_mode");
+    report.add_with_note(
+        "variables",
+        "sql_mode",
+        &sql_mode,
+        expect(sql_mode.contains("STRICT_TRANS_TABLES") || sql_mode.contains("STRICT_ALL_TABLES")),
+        fl!("health-sql-mode"),
+    );
+    report.add(
+        "variables",
+        "local_infile",
+        get("local_infile"),
+        expect(!var_is(vars, "local_infile", "ON")),
+    );
+    // removed in 8.0
+    if vars.contains_key("query_cache_type") {
+        report.add(
+            "variables",
+            "query_cache_type",
+            get("query_cache_type"),
+            expect(var_is(vars, "query_cache_type", "OFF")),
+        );
+    }
+    if let (Some(connected), Some(max)) = (
+        get_num(status, "Threads_connected"),
+        get_num(vars, "max_connections"),
+    ) {
+        if max > 0.0 {
+            report.add(
+                "variables",
+                "connection_usage",
+                format!("{}/{}", connected, max),
+                expect(connected / max <= CONN_USAGE_WARN),
+            );
+        }
+    }
+}
+
+/// collect one-shot diagnostic report of server and current database,
+/// checks which depend on sys schema are reported as unavailable if it is missing
+pub async fn check(session: &Session) -> Result<HealthReport> {
+    let mut report = HealthReport::default();
+    let status = session.global_status().await?;
+    let vars = session.global_variables().await?;
+
+    report.add(
+        "server",
+        "version",
+        session.server_version().await?,
+        Severity::Info,
+    );
+    if let Some(uptime) = get_num(&status, "Uptime") {
+        report.add(
+            "server",
+            "uptime",
+            format_uptime(uptime as u64),
+            Severity::Info,
+        );
+    }
+
+    if let (Some(reads), Some(requests)) = (
+        get_num(&status, "Innodb_buffer_pool_reads"),
+        get_num(&status, "Innodb_buffer_pool_read_requests"),
+    ) {
+        if requests > 0.0 {
+            let ratio = 1.0 - reads / requests;
+            let severity = if ratio < HIT_RATIO_CRIT {
+                Severity::Crit
+            } else if ratio < HIT_RATIO_WARN {
+                Severity::Warn
+            } else {
+                Severity::Ok
+            };
+            report.add(
+                "performance",
+                "buffer_pool_hit_ratio",
+                format!("{:.2}%", ratio * 100.0),
+                severity,
+            );
+        }
+    }
+    if let Some(slow) = get_num(&status, "Slow_queries") {
+        let severity = if slow > 0.0 {
+            Severity::Warn
+        } else {
+            Severity::Ok
+        };
+        report.add("performance", "slow_queries", slow, severity);
+    }
+
+    match session.replica_status().await {
+        Ok(status) => check_replication(&mut report, status),
+        Err(e) => report.unavailable("replication", "status", e),
+    }
+
+    match session.table_fragmentation().await {
+        Ok(tables) => {
+            let mut found = false;
+            for (table, free, size) in tables {
+                let ratio = free as f64 / (size + free).max(1) as f64;
+                if free >= FRAGMENT_MIN_BYTES && ratio >= FRAGMENT_RATIO {
+                    found = true;
+                    report.add_with_note(
+                        "tables",
+                        "fragmented",
+                        table,
+                        Severity::Warn,
+                        fl!(
+                            "health-fragmented",
+                            free = format_bytes(free),
+                            ratio = format!("{:.0}", ratio * 100.0)
+                        ),
+                    );
+                }
+            }
+            if !found {
+                report.add("tables", "fragmented", "-", Severity::Ok);
+            }
+        }
+        Err(e) => report.unavailable("tables", "fragmented", e),
+    }
+    match session.tables_without_pk().await {
+        Ok(tables) if tables.is_empty() => {
+            report.add("tables", "without_primary_key", "-", Severity::Ok)
+        }
+        Ok(tables) => {
+            for table in tables {
+                report.add("tables", "without_primary_key", table, Severity::Warn);
+            }
+        }
+        Err(e) => report.unavailable("tables", "without_primary_key", e),
+    }
+
+    match session.unused_indexes().await {
+        Ok(indexes) if indexes.is_empty() => report.add("indexes", "unused", "-", Severity::Ok),
+        Ok(indexes) => {
+            for (table, index) in indexes {
+                report.add(
+                    "indexes",
+                    "unused",
+                    format!("{}.{}", table, index),
+                    Severity::Warn,
+                );
+            }
+        }
+        Err(e) => report.unavailable("indexes", "unused", e),
+    }
+    match session.redundant_indexes().await {
+        Ok(indexes) if indexes.is_empty() => report.add("indexes", "duplicate", "-", Severity::Ok),
+        Ok(indexes) => {
+            for (table, redundant, dominant) in indexes {
+                report.add_with_note(
+                    "indexes",
+                    "duplicate",
+                    format!("{}.{}", table, redundant),
+                    Severity::Warn,
+                    fl!("health-covered-by", index = dominant),
+                );
+            }
+        }
+        Err(e) => report.unavailable("indexes", "duplicate", e),
+    }
+
+    check_variables(&mut report, &status, &vars);
+    Ok(report)
+}
@@ -0,0 +1,268 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, Local};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use structopt::StructOpt;
+
+use crate::{
+    config::{Config, Profile},
+    fl,
+    utils::{parse_time, read_file},
+};
+
+/// one line entered in shell
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// RFC 3339 local time the line was entered
+    pub time: String,
+    pub profile: String,
+    pub database: String,
+    pub statement: String,
+    pub duration_ms: u64,
+    pub success: bool,
+    pub rows: u64,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub struct HistoryOptions {
+    #[cfg_attr(feature = "zh-CN", doc = "连接配置名称")]
+    #[cfg_attr(feature = "en-US", doc = "profile name")]
+    #[structopt(short, long)]
+    pub profile: Option<String>,
+
+    #[cfg_attr(
+        feature = "zh-CN",
+        doc = "起始时间, 如 2021-05-01, RFC 3339 时间或 30m, 2h, 7d 表示多久之前"
+    )]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "start time, such as 2021-05-01, RFC 3339 time or 30m, 2h, 7d for time ago"
+    )]
+    #[structopt(long, parse(try_from_str = parse_time))]
+    pub since: Option<DateTime<FixedOffset>>,
+
+    #[cfg_attr(feature = "zh-CN", doc = "结束时间, 格式同 --since")]
+    #[cfg_attr(feature = "en-US", doc = "end time, same format as --since")]
+    #[structopt(long, parse(try_from_str = parse_time))]
+    pub until: Option<DateTime<FixedOffset>>,
+
+    #[cfg_attr(feature = "zh-CN", doc = "包含执行失败的语句")]
+    #[cfg_attr(feature = "en-US", doc = "include failed statements")]
+    #[structopt(short, long)]
+    pub all: bool,
+
+    #[cfg_attr(feature = "zh-CN", doc = "最多显示的记录数")]
+    #[cfg_attr(feature = "en-US", doc = "max entries to show")]
+    #[structopt(short = "n", long, default_value = "50")]
+    pub limit: usize,
+
+    #[cfg_attr(feature = "zh-CN", doc = "模糊匹配的文本, 指定时按匹配程度排序")]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "text to fuzzy match, entries are ranked by score if set"
+    )]
+    pub text: Option<String>,
+}
+
+/// JSON lines file of all profiles' history, index of an entry is its line number
+pub fn history_path() -> Result<PathBuf> {
+    let mut path = PathBuf::from(std::env::var("HOME").with_context(|| fl!("home-not-set"))?);
+    path.push(".dcli");
+    path.push("history");
+    if !path.exists() {
+        std::fs::create_dir_all(&path).with_context(|| fl!("create-his-dir-failed"))?
+    }
+    path.push("history.jsonl");
+    Ok(path)
+}
+
+/// all entries in time order
+pub fn load() -> Result<Vec<HistoryEntry>> {
+    let path = history_path()?;
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = read_file(&path.to_string_lossy()).with_context(|| fl!("load-his-failed"))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                log::warn!("skip invalid history entry: {}", e);
+                None
+            }
+        })
+        .collect())
+}
+
+fn append(path: &Path, entries: &[HistoryEntry]) -> Result<()> {
+    let mut content = String::new();
+    for entry in entries {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(content.as_bytes())?;
+    Ok(())
+}
+
+/// move plain text history of older versions, `<profile>_history.txt`,
+/// into structured history, the text file is renamed to `.migrated`
+fn migrate_legacy(profile: &Profile, path: &Path) -> Result<()> {
+    let legacy = path.with_file_name(format!("{}_history.txt", profile.name));
+    if !legacy.exists() {
+        return Ok(());
+    }
+    let time: DateTime<Local> = std::fs::metadata(&legacy)?.modified()?.into();
+    let entries: Vec<HistoryEntry> = read_file(&legacy.to_string_lossy())?
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| HistoryEntry {
+            time: time.to_rfc3339(),
+            profile: profile.name.clone(),
+            database: profile.db.clone(),
+            statement: line.to_string(),
+            duration_ms: 0,
+            success: true,
+            rows: 0,
+        })
+        .collect();
+    append(path, &entries)?;
+    std::fs::rename(&legacy, legacy.with_extension("txt.migrated"))?;
+    Ok(())
+}
+
+/// history of a shell session
+#[derive(Clone)]
+pub struct History {
+    path: PathBuf,
+    profile: String,
+    database: String,
+}
+
+impl History {
+    pub fn open(profile: &Profile) -> Result<Self> {
+        let path = history_path()?;
+        migrate_legacy(profile, &path).with_context(|| fl!("load-his-failed"))?;
+        Ok(Self {
+            path,
+            profile: profile.name.clone(),
+            database: profile.db.clone(),
+        })
+    }
+
+    /// lines of this profile to recall with arrow keys, failed ones are skipped
+    /// unless `recall_failed` is set
+    pub fn recall(&self, recall_failed: bool) -> Result<Vec<String>> {
+        Ok(load()?
+            .into_iter()
+            .filter(|e| e.profile == self.profile && (e.success || recall_failed))
+            .map(|e| e.statement)
+            .collect())
+    }
+
+    pub fn record(
+        &self,
+        statement: &str,
+        started: DateTime<Local>,
+        duration: Duration,
+        success: bool,
+        rows: u64,
+    ) {
+        let entry = HistoryEntry {
+            time: started.to_rfc3339(),
+            profile: self.profile.clone(),
+            database: self.database.clone(),
+            statement: statement.to_string(),
+            duration_ms: duration.as_millis() as u64,
+            success,
+            rows,
+        };
+        if let Err(e) = append(&self.path, &[entry]) {
+            log::warn!("failed to write history: {:#}", e);
+        }
+    }
+}
+
+/// entry by index shown in history list
+pub fn get(index: usize) -> Result<HistoryEntry> {
+    load()?
+        .into_iter()
+        .nth(index)
+        .with_context(|| fl!("his-not-found", index = index))
+}
+
+/// entries matching options with their index, ranked by fuzzy score if text is set,
+/// otherwise the latest ones in time order
+pub fn search(opts: &HistoryOptions) -> Result<Vec<(usize, HistoryEntry)>> {
+    let matcher = SkimMatcherV2::default();
+    let mut found: Vec<(i64, usize, HistoryEntry)> = load()?
+        .into_iter()
+        .enumerate()
+        .filter(|(_, e)| opts.all || e.success)
+        .filter(|(_, e)| opts.profile.as_ref().map_or(true, |p| &e.profile == p))
+        .filter(|(_, e)| {
+            if opts.since.is_none() && opts.until.is_none() {
+                return true;
+            }
+            match DateTime::parse_from_rfc3339(&e.time) {
+                Ok(time) => {
+                    opts.since.map_or(true, |since| time >= since)
+                        && opts.until.map_or(true, |until| time <= until)
+                }
+                Err(_) => false,
+            }
+        })
+        .filter_map(|(i, e)| match &opts.text {
+            Some(text) => matcher
+                .fuzzy_match(&e.statement, text)
+                .map(|score| (score, i, e)),
+            None => Some((0, i, e)),
+        })
+        .collect();
+    if opts.text.is_some() {
+        // best match first, later entry first on the same score
+        found.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+        found.truncate(opts.limit);
+    } else {
+        found.drain(..found.len().saturating_sub(opts.limit));
+    }
+    Ok(found.into_iter().map(|(_, i, e)| (i, e)).collect())
+}
+
+pub fn print(config: &Config, entries: Vec<(usize, HistoryEntry)>) {
+    let mut table = config.new_table();
+    table.set_header(vec![
+        "#",
+        "time",
+        "profile",
+        "database",
+        "duration_ms",
+        "rows",
+        "status",
+        "statement",
+    ]);
+    for (i, entry) in entries {
+        let status = if entry.success { "ok" } else { "failed" };
+        table.add_row(vec![
+            i.to_string(),
+            entry.time,
+            entry.profile,
+            entry.database,
+            entry.duration_ms.to_string(),
+            entry.rows.to_string(),
+            status.to_string(),
+            entry.statement,
+        ]);
+    }
+    println!("{}", table);
+}
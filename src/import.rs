@@ -0,0 +1,463 @@
+use aes::{
+    cipher::{generic_array::GenericArray, BlockDecrypt, KeyInit},
+    Aes128,
+};
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{config::Profile, fl, utils::read_file};
+
+/// where profiles are imported from
+#[derive(Debug, Clone, Copy)]
+pub enum ImportSource {
+    /// `~/.my.cnf` option file, `[client]` and named groups
+    MyCnf,
+    /// `~/.mylogin.cnf` written by `mysql_config_editor`
+    LoginPath,
+    /// `MYSQL_*` and `DCLI_PROFILE_<NAME>` environment variables
+    Env,
+    /// DBeaver `data-sources.json`
+    DBeaver,
+    /// json object of profile name to profile or connection url
+    Json,
+}
+
+impl FromStr for ImportSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let val = match &*s.to_ascii_lowercase() {
+            "mycnf" => ImportSource::MyCnf,
+            "login-path" => ImportSource::LoginPath,
+            "env" => ImportSource::Env,
+            "dbeaver" => ImportSource::DBeaver,
+            "json" => ImportSource::Json,
+            _ => return Err(anyhow!(fl!("invalid-value", val = s))),
+        };
+        Ok(val)
+    }
+}
+
+impl std::fmt::Display for ImportSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let val = match self {
+            ImportSource::MyCnf => "mycnf",
+            ImportSource::LoginPath => "login-path",
+            ImportSource::Env => "env",
+            ImportSource::DBeaver => "dbeaver",
+            ImportSource::Json => "json",
+        };
+        write!(f, "{}", val)
+    }
+}
+
+impl ImportSource {
+    /// file read when no path is given
+    fn default_path(&self) -> Result<Option<PathBuf>> {
+        let home = PathBuf::from(std::env::var("HOME").with_context(|| fl!("home-not-set"))?);
+        let path = match self {
+            ImportSource::MyCnf => Some(home.join(".my.cnf")),
+            ImportSource::LoginPath => Some(home.join(".mylogin.cnf")),
+            ImportSource::DBeaver => {
+                let linux = home.join(".local/share/DBeaverData");
+                let data = if linux.exists() {
+                    linux
+                } else {
+                    home.join("Library/DBeaverData")
+                };
+                Some(data.join("workspace6/General/.dbeaver/data-sources.json"))
+            }
+            ImportSource::Env | ImportSource::Json => None,
+        };
+        Ok(path)
+    }
+}
+
+/// read profiles from source, name of each profile is set
+pub fn import(source: ImportSource, path: Option<&Path>) -> Result<Vec<Profile>> {
+    let default = source.default_path()?;
+    let path = match (path, &default) {
+        (Some(path), _) => Some(path),
+        (None, Some(path)) => Some(path.as_path()),
+        (None, None) => None,
+    };
+    let profiles = match (source, path) {
+        (ImportSource::Env, _) => env_profiles()?
+            .into_iter()
+            .map(|(name, profile)| {
+                let name = name.strip_prefix("env:").unwrap_or(&name).to_string();
+                (name, profile)
+            })
+            .collect(),
+        (_, None) => {
+            return Err(anyhow!(fl!(
+                "import-path-required",
+                source = source.to_string()
+            )))
+        }
+        (ImportSource::MyCnf, Some(path)) => from_option_file(&read_file(&path.to_string_lossy())?),
+        (ImportSource::LoginPath, Some(path)) => from_option_file(&decrypt_login_path(path)?),
+        (ImportSource::DBeaver, Some(path)) => from_dbeaver(path)?,
+        (ImportSource::Json, Some(path)) => from_json(&read_file(&path.to_string_lossy())?)?,
+    };
+    Ok(profiles
+        .into_iter()
+        .map(|(name, mut profile)| {
+            profile.name = name;
+            profile
+        })
+        .collect())
+}
+
+/// option groups of mysql server and tools which are not connections
+fn is_server_group(group: &str) -> bool {
+    group.starts_with("mysqld")
+        || group.starts_with("mariadb")
+        || matches!(group, "server" | "embedded" | "galera" | "mysql_upgrade")
+}
+
+/// parse option file, values in `[client]` and `[mysql]` are shared by other groups
+fn parse_option_file(content: &str) -> Vec<(String, Vec<(String, String)>)> {
+    let mut groups: Vec<(String, Vec<(String, String)>)> = vec![];
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with(';')
+            || line.starts_with('!')
+        {
+            continue;
+        }
+        if let Some(group) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            groups.push((group.trim().to_ascii_lowercase(), vec![]));
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => (line, ""),
+        };
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            .unwrap_or(value);
+        if let Some((_, options)) = groups.last_mut() {
+            options.push((key.replace('_', "-"), value.to_string()));
+        }
+    }
+    groups
+}
+
+fn apply_option(profile: &mut Profile, key: &str, value: &str) {
+    match key {
+        "host" => profile.host = value.to_string(),
+        "port" => {
+            if let Ok(port) = value.parse() {
+                profile.port = port
+            }
+        }
+        "user" => profile.user = Some(value.to_string()),
+        "password" => profile.password = Some(value.to_string()),
+        "database" => profile.db = value.to_string(),
+        "socket" => profile.socket = Some(PathBuf::from(value)),
+        "ssl-mode" => profile.ssl_mode = value.parse().ok(),
+        "ssl-ca" => profile.ssl_ca = Some(PathBuf::from(value)),
+        _ => {}
+    }
+}
+
+/// each client group becomes a profile named after the group
+fn from_option_file(content: &str) -> Vec<(String, Profile)> {
+    let groups = parse_option_file(content);
+    let mut base = Profile::default();
+    for (group, options) in &groups {
+        if group == "client" || group == "mysql" {
+            for (key, value) in options {
+                apply_option(&mut base, key, value);
+            }
+        }
+    }
+    let mut profiles: Vec<(String, Profile)> = vec![];
+    if groups
+        .iter()
+        .any(|(group, _)| group == "client" || group == "mysql")
+    {
+        profiles.push(("client".to_string(), base.clone()));
+    }
+    for (group, options) in groups {
+        if group == "client" || group == "mysql" || is_server_group(&group) {
+            continue;
+        }
+        match profiles.iter_mut().find(|(name, _)| name == &group) {
+            Some((_, profile)) => options
+                .iter()
+                .for_each(|(key, value)| apply_option(profile, key, value)),
+            None => {
+                let mut profile = base.clone();
+                options
+                    .iter()
+                    .for_each(|(key, value)| apply_option(&mut profile, key, value));
+                profiles.push((group, profile));
+            }
+        }
+    }
+    profiles
+}
+
+/// `.mylogin.cnf` is an option file encrypted with AES-128-ECB,
+/// key is stored in the file header
+fn decrypt_login_path(path: &Path) -> Result<String> {
+    let invalid = || {
+        anyhow!(fl!(
+            "import-invalid-file",
+            file = path.to_string_lossy().to_string()
+        ))
+    };
+    let data = std::fs::read(path).with_context(|| {
+        fl!(
+            "open-file-failed",
+            file = path.to_string_lossy().to_string()
+        )
+    })?;
+    if data.len() < 24 {
+        return Err(invalid());
+    }
+    let mut key = [0u8; 16];
+    for (i, b) in data[4..24].iter().enumerate() {
+        key[i % 16] ^= b;
+    }
+    let cipher = Aes128::new(GenericArray::from_slice(&key));
+    let mut content = String::new();
+    let mut pos = 24;
+    while pos + 4 <= data.len() {
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&data[pos..pos + 4]);
+        let len = u32::from_le_bytes(len) as usize;
+        pos += 4;
+        let chunk = data.get(pos..pos + len).ok_or_else(invalid)?;
+        pos += len;
+        let mut plain = decrypt_blocks(&cipher, chunk, None).ok_or_else(invalid)?;
+        unpad(&mut plain).ok_or_else(invalid)?;
+        content.push_str(&String::from_utf8_lossy(&plain));
+    }
+    Ok(content)
+}
+
+/// decrypt whole blocks in ECB mode, or CBC mode if `iv` is set
+fn decrypt_blocks(cipher: &Aes128, data: &[u8], iv: Option<&[u8]>) -> Option<Vec<u8>> {
+    if data.len() % 16 != 0 {
+        return None;
+    }
+    let mut plain = Vec::with_capacity(data.len());
+    let mut prev = iv;
+    for block in data.chunks(16) {
+        let mut buf = GenericArray::clone_from_slice(block);
+        cipher.decrypt_block(&mut buf);
+        if let Some(prev) = prev {
+            buf.iter_mut().zip(prev).for_each(|(b, p)| *b ^= p);
+        }
+        plain.extend_from_slice(&buf);
+        if prev.is_some() {
+            prev = Some(block);
+        }
+    }
+    Some(plain)
+}
+
+/// strip PKCS#7 padding
+fn unpad(plain: &mut Vec<u8>) -> Option<()> {
+    let pad = *plain.last()? as usize;
+    if pad == 0 || pad > 16 || pad > plain.len() {
+        return None;
+    }
+    plain.truncate(plain.len() - pad);
+    Some(())
+}
+
+/// fixed key DBeaver uses to encrypt `credentials-config.json`
+const DBEAVER_KEY: [u8; 16] = [
+    0xba, 0xbb, 0x4a, 0x9f, 0x77, 0x4a, 0xb8, 0x53, 0xc9, 0x6c, 0x2d, 0x65, 0x3d, 0xfe, 0x54, 0x4a,
+];
+
+/// saved user and password of connections, keyed by connection id
+fn dbeaver_credentials(path: &Path) -> Option<Value> {
+    let data = std::fs::read(path).ok()?;
+    if data.len() < 32 {
+        return None;
+    }
+    let cipher = Aes128::new(GenericArray::from_slice(&DBEAVER_KEY));
+    let mut plain = decrypt_blocks(&cipher, &data[16..], Some(&data[..16]))?;
+    unpad(&mut plain)?;
+    serde_json::from_slice(&plain).ok()
+}
+
+fn json_str(value: &Value, key: &str) -> Option<String> {
+    match value.get(key)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// MySQL and MariaDB connections of DBeaver, with credentials and SSH tunnel if saved
+fn from_dbeaver(path: &Path) -> Result<Vec<(String, Profile)>> {
+    let content = read_file(&path.to_string_lossy())?;
+    let sources: Value = serde_json::from_str(&content).with_context(|| {
+        fl!(
+            "import-invalid-file",
+            file = path.to_string_lossy().to_string()
+        )
+    })?;
+    let credentials = path
+        .parent()
+        .and_then(|dir| dbeaver_credentials(&dir.join("credentials-config.json")))
+        .unwrap_or(Value::Null);
+    let mut profiles = vec![];
+    let connections = match sources.get("connections").and_then(Value::as_object) {
+        Some(connections) => connections,
+        None => return Ok(profiles),
+    };
+    for (id, conn) in connections {
+        if conn.get("provider").and_then(Value::as_str) != Some("mysql") {
+            continue;
+        }
+        let cfg = &conn["configuration"];
+        let auth = &credentials[id]["#connection"];
+        let mut profile = Profile::default();
+        if let Some(host) = json_str(cfg, "host") {
+            profile.host = host;
+        }
+        if let Some(port) = json_str(cfg, "port").and_then(|p| p.parse().ok()) {
+            profile.port = port;
+        }
+        profile.db = json_str(cfg, "database").unwrap_or_default();
+        profile.user = json_str(auth, "user").or_else(|| json_str(cfg, "user"));
+        profile.password = json_str(auth, "password").or_else(|| json_str(cfg, "password"));
+        let ssh = &cfg["handlers"]["ssh_tunnel"];
+        if ssh["enabled"].as_bool() == Some(true) {
+            let props = &ssh["properties"];
+            profile.ssh_host = json_str(props, "host");
+            profile.ssh_port = json_str(props, "port").and_then(|p| p.parse().ok());
+            profile.ssh_user = json_str(&credentials[id]["network/ssh_tunnel"], "user")
+                .or_else(|| json_str(ssh, "user"));
+            profile.ssh_key = json_str(props, "keyPath").map(PathBuf::from);
+        }
+        let name = json_str(conn, "name").unwrap_or_else(|| id.clone());
+        let name = name.split_whitespace().collect::<Vec<&str>>().join("_");
+        profiles.push((name, profile));
+    }
+    Ok(profiles)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonProfile {
+    Url(String),
+    Profile(Box<Profile>),
+}
+
+/// object of profile name to connection url or profile in config file format
+fn from_json(content: &str) -> Result<Vec<(String, Profile)>> {
+    let parsed: BTreeMap<String, JsonProfile> =
+        serde_json::from_str(content).with_context(|| fl!("import-invalid-json"))?;
+    let mut profiles = vec![];
+    for (name, profile) in parsed {
+        let profile = match profile {
+            JsonProfile::Url(url) => {
+                let mut profile = Profile::default();
+                profile.set_url(&url)?;
+                profile
+            }
+            JsonProfile::Profile(profile) => *profile,
+        };
+        profiles.push((name, profile));
+    }
+    Ok(profiles)
+}
+
+/// profile from `<prefix>_URL`, `<prefix>_HOST`, `<prefix>_PWD` etc, none if no variable is set
+fn from_env(prefix: &str) -> Result<Option<Profile>> {
+    let var = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| std::env::var(format!("{}_{}", prefix, name)).ok())
+    };
+    let mut profile = Profile::default();
+    let mut found = false;
+    if let Some(url) = var(&["URL"]) {
+        profile
+            .set_url(&url)
+            .with_context(|| fl!("import-invalid-env-url", var = format!("{}_URL", prefix)))?;
+        found = true;
+    }
+    let options = [
+        ("host", var(&["HOST"])),
+        ("port", var(&["TCP_PORT", "PORT"])),
+        ("user", var(&["USER"])),
+        ("password", var(&["PWD", "PASSWORD"])),
+        ("database", var(&["DATABASE", "DB"])),
+        ("socket", var(&["UNIX_PORT", "SOCKET"])),
+    ];
+    for (key, value) in options.iter() {
+        if let Some(value) = value {
+            apply_option(&mut profile, key, value);
+            found = true;
+        }
+    }
+    if found {
+        Ok(Some(profile))
+    } else {
+        Ok(None)
+    }
+}
+
+/// ephemeral profiles, `env` from `MYSQL_*` variables, `env:<name>` from `DCLI_PROFILE_<NAME>`
+/// which is a connection url
+pub fn env_profiles() -> Result<HashMap<String, Profile>> {
+    match load_env_profiles() {
+        (_, Some(e)) => Err(e),
+        (profiles, None) => Ok(profiles),
+    }
+}
+
+/// like `env_profiles`, but invalid `MYSQL_*` variables don't fail, their error is
+/// returned to be reported when `env` profile is used
+pub fn load_env_profiles() -> (HashMap<String, Profile>, Option<anyhow::Error>) {
+    let mut profiles = HashMap::new();
+    let mut error = None;
+    match from_env("MYSQL") {
+        Ok(Some(profile)) => {
+            profiles.insert("env".to_string(), profile);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            log::warn!("skip env profile: {:#}", e);
+            error = Some(e);
+        }
+    }
+    for (key, value) in std::env::vars() {
+        if let Some(name) = key.strip_prefix("DCLI_PROFILE_") {
+            let mut profile = Profile::default();
+            match profile.set_url(&value) {
+                Ok(_) => {
+                    profiles.insert(format!("env:{}", name.to_ascii_lowercase()), profile);
+                }
+                Err(e) => log::warn!("skip {}: {:#}", key, e),
+            }
+        }
+    }
+    let profiles = profiles
+        .into_iter()
+        .map(|(name, mut profile)| {
+            profile.name = name.clone();
+            (name, profile)
+        })
+        .collect();
+    (profiles, error)
+}
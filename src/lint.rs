@@ -0,0 +1,444 @@
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::{fl, guard};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Severity {
+    #[serde(rename = "warning")]
+    Warning,
+    #[serde(rename = "error")]
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let val = match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}", val)
+    }
+}
+
+/// problem found by linter, line and column start from 1
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {} [{}] {}",
+            self.file, self.line, self.column, self.severity, self.rule, self.message
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Word,
+    /// identifier quoted with backtick
+    Ident,
+    /// string or number
+    Literal,
+    Symbol,
+}
+
+#[derive(Debug, Clone)]
+struct Token<'a> {
+    kind: Kind,
+    text: &'a str,
+    /// byte offsets in source
+    start: usize,
+    end: usize,
+}
+
+impl<'a> Token<'a> {
+    fn is_word(&self, word: &str) -> bool {
+        self.kind == Kind::Word && self.text.eq_ignore_ascii_case(word)
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        self.kind == Kind::Symbol && self.text == symbol
+    }
+}
+
+/// split sql into tokens, comments and whitespace are dropped
+fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let chars: Vec<(usize, char)> = sql.char_indices().collect();
+    let offset = |i: usize| chars.get(i).map(|(o, _)| *o).unwrap_or(sql.len());
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].1;
+        let next = chars.get(i + 1).map(|(_, c)| *c);
+        let start = i;
+        let kind = if c.is_whitespace() {
+            i += 1;
+            continue;
+        } else if c == '#' || (c == '-' && next == Some('-')) {
+            // `--` starts a comment only if followed by whitespace in MySQL
+            if c == '-' && !chars.get(i + 2).map_or(true, |(_, c)| c.is_whitespace()) {
+                i += 1;
+                Kind::Symbol
+            } else {
+                while i < chars.len() && chars[i].1 != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len()
+                && !(chars[i].1 == '*' && chars.get(i + 1).map(|(_, c)| *c) == Some('/'))
+            {
+                i += 1;
+            }
+            i = (i + 2).min(chars.len());
+            continue;
+        } else if c == '\'' || c == '"' || c == '`' {
+            i += 1;
+            while i < chars.len() {
+                match chars[i].1 {
+                    '\\' if c != '`' => i += 2,
+                    q if q == c => {
+                        // doubled quote is an escaped quote
+                        if chars.get(i + 1).map(|(_, c)| *c) == Some(c) {
+                            i += 2;
+                        } else {
+                            break;
+                        }
+                    }
+                    _ => i += 1,
+                }
+            }
+            i = (i + 1).min(chars.len());
+            if c == '`' {
+                Kind::Ident
+            } else {
+                Kind::Literal
+            }
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].1.is_ascii_alphanumeric() || chars[i].1 == '.') {
+                i += 1;
+            }
+            Kind::Literal
+        } else if c.is_alphanumeric() || c == '_' || c == '$' || c == '@' {
+            while i < chars.len()
+                && (chars[i].1.is_alphanumeric() || matches!(chars[i].1, '_' | '$' | '@'))
+            {
+                i += 1;
+            }
+            Kind::Word
+        } else {
+            i += 1;
+            Kind::Symbol
+        };
+        tokens.push(Token {
+            kind,
+            text: &sql[offset(start)..offset(i)],
+            start: offset(start),
+            end: offset(i),
+        });
+    }
+    tokens
+}
+
+/// words before `(` which don't make a function call
+const NOT_FUNCTIONS: &[&str] = &[
+    "ALL", "AND", "ANY", "AS", "EXISTS", "FROM", "IN", "INTO", "JOIN", "NOT", "ON", "OR", "OVER",
+    "SELECT", "SOME", "USING", "VALUES", "WHERE", "WITH",
+];
+
+/// words which are not column names in function arguments
+const NOT_COLUMNS: &[&str] = &[
+    "AND",
+    "AS",
+    "BINARY",
+    "BOTH",
+    "CASE",
+    "CHAR",
+    "DATE",
+    "DAY",
+    "DAY_HOUR",
+    "DAY_MINUTE",
+    "DAY_SECOND",
+    "DECIMAL",
+    "DISTINCT",
+    "ELSE",
+    "END",
+    "FALSE",
+    "FOR",
+    "FROM",
+    "HOUR",
+    "INTERVAL",
+    "IS",
+    "LEADING",
+    "LIKE",
+    "MICROSECOND",
+    "MINUTE",
+    "MONTH",
+    "NOT",
+    "NULL",
+    "OR",
+    "QUARTER",
+    "SECOND",
+    "SEPARATOR",
+    "SIGNED",
+    "THEN",
+    "TIME",
+    "TRAILING",
+    "TRUE",
+    "UNSIGNED",
+    "USING",
+    "WEEK",
+    "WHEN",
+    "YEAR",
+    "YEAR_MONTH",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Clause {
+    None,
+    Select,
+    From,
+    Where,
+    Other,
+}
+
+/// state of one level of parentheses
+struct Frame<'a> {
+    clause: Clause,
+    /// index of name token if the parentheses are arguments of a function
+    function: Option<usize>,
+    /// first column found in arguments, including nested calls
+    column: Option<&'a str>,
+}
+
+impl<'a> Frame<'a> {
+    fn new(function: Option<usize>) -> Self {
+        Self {
+            clause: Clause::None,
+            function,
+            column: None,
+        }
+    }
+}
+
+struct Linter<'a> {
+    file: &'a str,
+    sql: &'a str,
+    /// indexed columns in lower case, all columns are checked if not set
+    indexed: Option<&'a HashSet<String>>,
+    findings: Vec<Finding>,
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, token: &Token, severity: Severity, rule: &'static str, message: String) {
+        let before = &self.sql[..token.start];
+        let line = before.matches('\n').count() + 1;
+        let column = before[before.rfind('\n').map(|i| i + 1).unwrap_or(0)..]
+            .chars()
+            .count()
+            + 1;
+        self.findings.push(Finding {
+            file: self.file.to_string(),
+            line,
+            column,
+            severity,
+            rule,
+            message,
+        });
+    }
+
+    fn is_column(&self, tokens: &[Token<'a>], i: usize) -> Option<&'a str> {
+        let token = &tokens[i];
+        let followed_by_paren = tokens
+            .get(i + 1)
+            .is_some_and(|t| t.is_symbol("(") && t.start == token.end);
+        let qualified = i > 0 && tokens[i - 1].is_symbol(".");
+        let name = match token.kind {
+            Kind::Ident => token.text.trim_matches('`'),
+            Kind::Word
+                if !followed_by_paren
+                    && !token.text.starts_with('@')
+                    && (qualified || !NOT_COLUMNS.contains(&&*token.text.to_ascii_uppercase())) =>
+            {
+                token.text
+            }
+            _ => return None,
+        };
+        // qualifier of a column, such as `t` of `t.id`
+        if tokens.get(i + 1).is_some_and(|t| t.is_symbol(".")) {
+            return None;
+        }
+        match self.indexed {
+            Some(indexed) if !indexed.contains(&name.to_ascii_lowercase()) => None,
+            _ => Some(name),
+        }
+    }
+
+    fn statement(&mut self, tokens: &[Token<'a>]) {
+        let first = match tokens.first() {
+            Some(first) => first,
+            None => return,
+        };
+        if first.is_word("UPDATE") || first.is_word("DELETE") {
+            self.check_write(first, tokens);
+        }
+        let mut frames = vec![Frame::new(None)];
+        for (i, token) in tokens.iter().enumerate() {
+            let prev = if i > 0 { Some(&tokens[i - 1]) } else { None };
+            if token.is_symbol("(") {
+                let is_function = prev.is_some_and(|p| {
+                    p.kind == Kind::Word
+                        && p.end == token.start
+                        && !NOT_FUNCTIONS.contains(&&*p.text.to_ascii_uppercase())
+                });
+                frames.push(Frame::new(if is_function { Some(i - 1) } else { None }));
+                continue;
+            }
+            if token.is_symbol(")") {
+                if frames.len() > 1 {
+                    let frame = frames.pop().unwrap();
+                    let parent = frames.last_mut().unwrap();
+                    if let (Some(function), Some(column)) = (frame.function, frame.column) {
+                        if parent.clause == Clause::Where {
+                            let function = &tokens[function];
+                            self.report(
+                                function,
+                                Severity::Warning,
+                                "function-on-column",
+                                fl!(
+                                    "lint-function-on-column",
+                                    function = function.text,
+                                    column = column
+                                ),
+                            );
+                        } else if parent.function.is_some() && parent.column.is_none() {
+                            parent.column = Some(column);
+                        }
+                    }
+                }
+                continue;
+            }
+            let frame = frames.last_mut().unwrap();
+            if frame.function.is_some() {
+                if frame.column.is_none() {
+                    frame.column = self.is_column(tokens, i);
+                }
+                continue;
+            }
+            match token.kind {
+                Kind::Word => match &*token.text.to_ascii_uppercase() {
+                    "SELECT" => frame.clause = Clause::Select,
+                    "FROM" => frame.clause = Clause::From,
+                    "WHERE" => frame.clause = Clause::Where,
+                    // join condition is part of FROM clause
+                    "ON" if frame.clause == Clause::From => {}
+                    "GROUP" | "HAVING" | "ORDER" | "LIMIT" | "UNION" | "SET" | "VALUES"
+                    | "INTO" | "ON" | "FOR" | "WINDOW" => frame.clause = Clause::Other,
+                    // nullability of the subquery column is not known without schema,
+                    // so it's a reminder rather than a proven problem
+                    "NOT"
+                        if tokens.get(i + 1).is_some_and(|t| t.is_word("IN"))
+                            && tokens.get(i + 2).is_some_and(|t| t.is_symbol("("))
+                            && tokens.get(i + 3).is_some_and(|t| t.is_word("SELECT")) =>
+                    {
+                        self.report(
+                            token,
+                            Severity::Warning,
+                            "not-in-subquery-null",
+                            fl!("lint-not-in-subquery-null"),
+                        );
+                    }
+                    _ => {}
+                },
+                Kind::Symbol if token.is_symbol("*") && frame.clause == Clause::Select => {
+                    let after_item_start = prev.is_some_and(|p| {
+                        p.is_word("SELECT")
+                            || p.is_word("DISTINCT")
+                            || p.is_symbol(",")
+                            || p.is_symbol(".")
+                    });
+                    if after_item_start {
+                        self.report(
+                            token,
+                            Severity::Warning,
+                            "select-star",
+                            fl!("lint-select-star"),
+                        );
+                    }
+                }
+                Kind::Symbol if token.is_symbol(",") && frame.clause == Clause::From => {
+                    self.report(
+                        token,
+                        Severity::Warning,
+                        "implicit-cross-join",
+                        fl!("lint-implicit-cross-join"),
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// UPDATE or DELETE should be limited, and never run without WHERE,
+    /// the same check as the one guarding statements of profiles
+    fn check_write(&mut self, first: &Token, tokens: &[Token]) {
+        let last = tokens.last().unwrap_or(first);
+        let statement = first.text.to_ascii_uppercase();
+        if guard::is_unbounded(&self.sql[first.start..last.end]) {
+            self.report(
+                first,
+                Severity::Error,
+                "unbounded-write",
+                fl!("lint-unbounded-write", statement = statement),
+            );
+            return;
+        }
+        let mut depth = 0;
+        let mut has_limit = false;
+        for token in tokens {
+            if token.is_symbol("(") {
+                depth += 1;
+            } else if token.is_symbol(")") {
+                depth -= 1;
+            } else if depth == 0 && token.is_word("LIMIT") {
+                has_limit = true;
+            }
+        }
+        if !has_limit {
+            self.report(
+                first,
+                Severity::Warning,
+                "write-without-limit",
+                fl!("lint-write-without-limit", statement = statement),
+            );
+        }
+    }
+}
+
+/// check statements in sql, `file` is the name shown in findings,
+/// function calls are checked on indexed columns only if `indexed` is set
+pub fn lint(file: &str, sql: &str, indexed: Option<&HashSet<String>>) -> Vec<Finding> {
+    let tokens = tokenize(sql);
+    let mut linter = Linter {
+        file,
+        sql,
+        indexed,
+        findings: vec![],
+    };
+    for statement in tokens.split(|t| t.is_symbol(";")) {
+        linter.statement(statement);
+    }
+    linter.findings
+}
@@ -21,12 +21,30 @@ use structopt::StructOpt;
 #[folder = "i18n"]
 struct Translations;
 
+pub mod audit;
 pub mod cli;
+pub mod codegen;
 pub mod config;
+pub mod erd;
+pub mod federate;
+pub mod formatter;
+pub mod group;
+pub mod guard;
+pub mod health;
+pub mod history;
+pub mod import;
+pub mod lint;
+pub mod migrate;
 pub mod mysql;
 pub mod output;
-pub mod utils;
 pub mod query;
+pub mod snippet;
+pub mod template;
+pub mod theme;
+pub mod top;
+pub mod tunnel;
+pub mod utils;
+pub mod watch;
 
 pub static LOADER: Lazy<Arc<Mutex<FluentLanguageLoader>>> = Lazy::new(|| {
     let translations = Translations {};
@@ -53,11 +71,15 @@ async fn main() -> Result<()> {
     let cmd = DCliCommand::from_args();
     let mut config = Config::load()?;
     init_log(&config);
+    audit::init(&config);
+    template::init(&config);
+    theme::init(&config);
     if let Some(lang) = &config.lang {
         utils::reset_loader(lang)
     }
-    cmd.run(&mut config).await?;
-    Ok(())
+    let result = cmd.run(&mut config).await;
+    tunnel::close_all();
+    result
 }
 
 fn init_log(config: &Config) {
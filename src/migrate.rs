@@ -0,0 +1,154 @@
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{fl, utils::read_file};
+
+/// table to record applied migrations
+pub const MIGRATION_TABLE: &str = "_dcli_migrations";
+
+/// a versioned migration, loaded from `<version>_<name>.up.sql` and optional
+/// `<version>_<name>.down.sql`, plain `<version>_<name>.sql` is treated as up migration
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub version: i64,
+    pub name: String,
+    pub up: String,
+    pub down: Option<String>,
+}
+
+/// migration record in tracking table
+#[derive(Debug, Clone)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub checksum: String,
+    pub applied_at: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// migration file changed after applied
+    Modified,
+    /// applied migration file not found
+    Missing,
+}
+
+impl std::fmt::Display for MigrationState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let val = match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "modified",
+            MigrationState::Missing => "missing",
+        };
+        write!(f, "{}", val)
+    }
+}
+
+impl Migration {
+    /// sha256 of up migration content
+    pub fn checksum(&self) -> String {
+        format!("{:x}", Sha256::digest(self.up.as_bytes()))
+    }
+
+    /// load all migrations in dir, sorted by version
+    pub fn load_dir(dir: &Path) -> Result<Vec<Migration>> {
+        let entries = std::fs::read_dir(dir)
+            .with_context(|| fl!("open-file-failed", file = dir.to_string_lossy().to_string()))?;
+        let mut migrations: BTreeMap<i64, Migration> = BTreeMap::new();
+        for entry in entries {
+            let path = entry?.path();
+            let file_name = match path.file_name().and_then(|f| f.to_str()) {
+                Some(name) if name.ends_with(".sql") => name.trim_end_matches(".sql"),
+                _ => continue,
+            };
+            let (stem, is_down) = if let Some(stem) = file_name.strip_suffix(".down") {
+                (stem, true)
+            } else {
+                (file_name.trim_end_matches(".up"), false)
+            };
+            let (version, name) = match stem.split_once('_') {
+                Some((version, name)) => (version, name),
+                None => (stem, ""),
+            };
+            let version: i64 = match version.parse() {
+                Ok(v) => v,
+                Err(_) => {
+                    log::warn!("skip migration file {:?}, version is not a number", path);
+                    continue;
+                }
+            };
+            let content = read_file(&path.to_string_lossy())?;
+            let migration = migrations.entry(version).or_insert_with(|| Migration {
+                version,
+                name: name.to_string(),
+                up: String::new(),
+                down: None,
+            });
+            if migration.name != name {
+                return Err(anyhow!(fl!(
+                    "migration-version-conflict",
+                    version = version.to_string()
+                )));
+            }
+            if is_down {
+                migration.down = Some(content);
+            } else {
+                migration.up = content;
+            }
+        }
+        Ok(migrations.into_values().collect())
+    }
+
+    /// create empty up and down migration files with timestamp version, return created paths
+    pub fn create(dir: &Path, name: &str) -> Result<(PathBuf, PathBuf)> {
+        std::fs::create_dir_all(dir).with_context(|| {
+            fl!(
+                "write-file-failed",
+                file = dir.to_string_lossy().to_string()
+            )
+        })?;
+        let version = chrono::Local::now().format("%Y%m%d%H%M%S");
+        let name = name.replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+        let up = dir.join(format!("{}_{}.up.sql", version, name));
+        let down = dir.join(format!("{}_{}.down.sql", version, name));
+        for (path, header) in [(&up, "up"), (&down, "down")] {
+            std::fs::write(path, format!("-- {} {}\n", name, header)).with_context(|| {
+                fl!(
+                    "write-file-failed",
+                    file = path.to_string_lossy().to_string()
+                )
+            })?;
+        }
+        Ok((up, down))
+    }
+}
+
+/// compare migration files with applied records, return state of each version in order
+pub fn migration_states(
+    migrations: &[Migration],
+    applied: &[AppliedMigration],
+) -> Vec<(i64, String, MigrationState)> {
+    let mut states: BTreeMap<i64, (String, MigrationState)> = BTreeMap::new();
+    for m in migrations {
+        states.insert(m.version, (m.name.clone(), MigrationState::Pending));
+    }
+    for a in applied {
+        let state = match migrations.iter().find(|m| m.version == a.version) {
+            Some(m) if m.checksum() == a.checksum => MigrationState::Applied,
+            Some(_) => MigrationState::Modified,
+            None => MigrationState::Missing,
+        };
+        states.insert(a.version, (a.name.clone(), state));
+    }
+    states
+        .into_iter()
+        .map(|(version, (name, state))| (version, name, state))
+        .collect()
+}
@@ -389,4 +389,154 @@ pub const KEYWORDS: [&str; 389] = [
     "YEAR",
     "ZONE",
 ];
+/// built-in functions with their arguments, for signature hints in shell
+pub const FUNCTIONS: [(&str, &str); 138] = [
+    ("ABS", "x"),
+    ("ADDDATE", "date, INTERVAL expr unit"),
+    ("AES_DECRYPT", "crypt_str, key_str"),
+    ("AES_ENCRYPT", "str, key_str"),
+    ("ANY_VALUE", "arg"),
+    ("ASCII", "str"),
+    ("AVG", "[DISTINCT] expr"),
+    ("BIT_LENGTH", "str"),
+    ("CAST", "expr AS type"),
+    ("CEIL", "x"),
+    ("CEILING", "x"),
+    ("CHAR_LENGTH", "str"),
+    ("COALESCE", "value, ..."),
+    ("CONCAT", "str1, str2, ..."),
+    ("CONCAT_WS", "separator, str1, str2, ..."),
+    ("CONVERT", "expr, type"),
+    ("CONVERT_TZ", "dt, from_tz, to_tz"),
+    ("COUNT", "[DISTINCT] expr"),
+    ("CURDATE", ""),
+    ("CURRENT_TIMESTAMP", "[fsp]"),
+    ("CURTIME", "[fsp]"),
+    ("DATABASE", ""),
+    ("DATE", "expr"),
+    ("DATEDIFF", "expr1, expr2"),
+    ("DATE_ADD", "date, INTERVAL expr unit"),
+    ("DATE_FORMAT", "date, format"),
+    ("DATE_SUB", "date, INTERVAL expr unit"),
+    ("DAY", "date"),
+    ("DAYNAME", "date"),
+    ("DAYOFMONTH", "date"),
+    ("DAYOFWEEK", "date"),
+    ("DAYOFYEAR", "date"),
+    ("DENSE_RANK", ""),
+    ("ELT", "n, str1, str2, ..."),
+    ("EXTRACT", "unit FROM date"),
+    ("FIELD", "str, str1, str2, ..."),
+    ("FIND_IN_SET", "str, strlist"),
+    ("FIRST_VALUE", "expr"),
+    ("FLOOR", "x"),
+    ("FORMAT", "x, d[, locale]"),
+    ("FROM_BASE64", "str"),
+    ("FROM_UNIXTIME", "unix_timestamp[, format]"),
+    ("GREATEST", "value1, value2, ..."),
+    (
+        "GROUP_CONCAT",
+        "[DISTINCT] expr [ORDER BY ...] [SEPARATOR str]",
+    ),
+    ("HEX", "str"),
+    ("HOUR", "time"),
+    ("IF", "expr1, expr2, expr3"),
+    ("IFNULL", "expr1, expr2"),
+    ("INET_ATON", "expr"),
+    ("INET_NTOA", "expr"),
+    ("INSERT", "str, pos, len, newstr"),
+    ("INSTR", "str, substr"),
+    ("JSON_ARRAY", "[val, ...]"),
+    ("JSON_ARRAYAGG", "col_or_expr"),
+    ("JSON_CONTAINS", "target, candidate[, path]"),
+    ("JSON_EXTRACT", "json_doc, path, ..."),
+    ("JSON_KEYS", "json_doc[, path]"),
+    ("JSON_LENGTH", "json_doc[, path]"),
+    ("JSON_OBJECT", "[key, val, ...]"),
+    ("JSON_OBJECTAGG", "key, value"),
+    ("JSON_SET", "json_doc, path, val, ..."),
+    ("JSON_UNQUOTE", "json_val"),
+    ("LAG", "expr[, n[, default]]"),
+    ("LAST_DAY", "date"),
+    ("LAST_INSERT_ID", "[expr]"),
+    ("LAST_VALUE", "expr"),
+    ("LCASE", "str"),
+    ("LEAD", "expr[, n[, default]]"),
+    ("LEAST", "value1, value2, ..."),
+    ("LEFT", "str, len"),
+    ("LENGTH", "str"),
+    ("LOCATE", "substr, str[, pos]"),
+    ("LOWER", "str"),
+    ("LPAD", "str, len, padstr"),
+    ("LTRIM", "str"),
+    ("MAX", "[DISTINCT] expr"),
+    ("MD5", "str"),
+    ("MIN", "[DISTINCT] expr"),
+    ("MINUTE", "time"),
+    ("MOD", "n, m"),
+    ("MONTH", "date"),
+    ("MONTHNAME", "date"),
+    ("NOW", "[fsp]"),
+    ("NTILE", "n"),
+    ("NULLIF", "expr1, expr2"),
+    ("PERIOD_DIFF", "p1, p2"),
+    ("POW", "x, y"),
+    ("POWER", "x, y"),
+    ("RAND", "[seed]"),
+    ("RANK", ""),
+    ("REGEXP_LIKE", "expr, pat[, match_type]"),
+    (
+        "REGEXP_REPLACE",
+        "expr, pat, repl[, pos[, occurrence[, match_type]]]",
+    ),
+    (
+        "REGEXP_SUBSTR",
+        "expr, pat[, pos[, occurrence[, match_type]]]",
+    ),
+    ("REPEAT", "str, count"),
+    ("REPLACE", "str, from_str, to_str"),
+    ("REVERSE", "str"),
+    ("RIGHT", "str, len"),
+    ("ROUND", "x[, d]"),
+    ("ROW_NUMBER", ""),
+    ("RPAD", "str, len, padstr"),
+    ("RTRIM", "str"),
+    ("SECOND", "time"),
+    ("SEC_TO_TIME", "seconds"),
+    ("SHA1", "str"),
+    ("SHA2", "str, hash_length"),
+    ("SIGN", "x"),
+    ("SLEEP", "duration"),
+    ("SPACE", "n"),
+    ("STR_TO_DATE", "str, format"),
+    ("SUBDATE", "date, INTERVAL expr unit"),
+    ("SUBSTR", "str, pos[, len]"),
+    ("SUBSTRING", "str, pos[, len]"),
+    ("SUBSTRING_INDEX", "str, delim, count"),
+    ("SUM", "[DISTINCT] expr"),
+    ("SYSDATE", "[fsp]"),
+    ("TIMEDIFF", "expr1, expr2"),
+    ("TIMESTAMP", "expr[, expr2]"),
+    ("TIMESTAMPADD", "unit, interval, datetime_expr"),
+    ("TIMESTAMPDIFF", "unit, datetime_expr1, datetime_expr2"),
+    ("TIME_FORMAT", "time, format"),
+    ("TIME_TO_SEC", "time"),
+    ("TO_BASE64", "str"),
+    ("TO_DAYS", "date"),
+    ("TRIM", "[{BOTH | LEADING | TRAILING} [remstr] FROM] str"),
+    ("TRUNCATE", "x, d"),
+    ("UCASE", "str"),
+    ("UNHEX", "str"),
+    ("UNIX_TIMESTAMP", "[date]"),
+    ("UPPER", "str"),
+    ("USER", ""),
+    ("UTC_DATE", ""),
+    ("UTC_TIMESTAMP", "[fsp]"),
+    ("UUID", ""),
+    ("VERSION", ""),
+    ("WEEK", "date[, mode]"),
+    ("WEEKDAY", "date"),
+    ("YEAR", "date"),
+    ("YEARWEEK", "date[, mode]"),
+];
 pub const SCHEMA_TABLE: &str = "information_schema";
@@ -0,0 +1,88 @@
+use super::{Session, SCHEMA_TABLE};
+use crate::fl;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+
+impl Session {
+    pub async fn server_version(&self) -> Result<String> {
+        let (version,): (String,) = sqlx::query_as("SELECT VERSION()")
+            .fetch_one(&self.pool)
+            .await
+            .with_context(|| fl!("monitor-query-failed"))?;
+        Ok(version)
+    }
+
+    /// replica status as column -> value, `None` if server is not a replica,
+    /// fallback to `SHOW SLAVE STATUS` for servers before 8.0.22
+    pub async fn replica_status(&self) -> Result<Option<HashMap<String, String>>> {
+        let output = match self.query("SHOW REPLICA STATUS").await {
+            Ok(output) => output,
+            Err(_) => self
+                .query("SHOW SLAVE STATUS")
+                .await
+                .with_context(|| fl!("monitor-query-failed"))?,
+        };
+        let header = output.header();
+        Ok(output
+            .values()
+            .into_iter()
+            .next()
+            .map(|row| header.into_iter().zip(row).collect()))
+    }
+
+    /// tables of current database with free space, as (table, data_free, data + index length)
+    pub async fn table_fragmentation(&self) -> Result<Vec<(String, u64, u64)>> {
+        let sql = format!(
+            "SELECT TABLE_NAME, CAST(DATA_FREE AS UNSIGNED), \
+             CAST(DATA_LENGTH + INDEX_LENGTH AS UNSIGNED) \
+             FROM {}.TABLES WHERE TABLE_SCHEMA = DATABASE() AND DATA_FREE > 0 \
+             ORDER BY DATA_FREE DESC",
+            SCHEMA_TABLE
+        );
+        sqlx::query_as(&sql)
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| fl!("monitor-query-failed"))
+    }
+
+    /// base tables of current database without primary key
+    pub async fn tables_without_pk(&self) -> Result<Vec<String>> {
+        let sql = format!(
+            "SELECT t.TABLE_NAME FROM {0}.TABLES t \
+             LEFT JOIN {0}.TABLE_CONSTRAINTS c ON c.TABLE_SCHEMA = t.TABLE_SCHEMA \
+             AND c.TABLE_NAME = t.TABLE_NAME AND c.CONSTRAINT_TYPE = 'PRIMARY KEY' \
+             WHERE t.TABLE_SCHEMA = DATABASE() AND t.TABLE_TYPE = 'BASE TABLE' \
+             AND c.CONSTRAINT_NAME IS NULL ORDER BY t.TABLE_NAME",
+            SCHEMA_TABLE
+        );
+        let rows: Vec<(String,)> = sqlx::query_as(&sql)
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| fl!("monitor-query-failed"))?;
+        Ok(rows.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// indexes never used since server start as (table, index), requires sys schema
+    pub async fn unused_indexes(&self) -> Result<Vec<(String, String)>> {
+        sqlx::query_as(
+            "SELECT object_name, index_name FROM sys.schema_unused_indexes \
+             WHERE object_schema = DATABASE() ORDER BY object_name, index_name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| fl!("monitor-query-failed"))
+    }
+
+    /// indexes covered by another index as (table, redundant index, dominant index),
+    /// requires sys schema
+    pub async fn redundant_indexes(&self) -> Result<Vec<(String, String, String)>> {
+        sqlx::query_as(
+            "SELECT table_name, redundant_index_name, dominant_index_name \
+             FROM sys.schema_redundant_indexes \
+             WHERE table_schema = DATABASE() ORDER BY table_name, redundant_index_name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| fl!("monitor-query-failed"))
+    }
+}
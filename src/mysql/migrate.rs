@@ -0,0 +1,105 @@
+use super::{Session, SCHEMA_TABLE};
+use crate::{
+    fl,
+    migrate::{AppliedMigration, Migration, MIGRATION_TABLE},
+    utils::split_sql,
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+
+impl Session {
+    /// create migration tracking table if not exists
+    pub async fn ensure_migration_table(&self) -> Result<()> {
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS `{}` (\
+             version BIGINT NOT NULL PRIMARY KEY, \
+             name VARCHAR(255) NOT NULL, \
+             checksum CHAR(64) NOT NULL, \
+             applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, \
+             execution_ms BIGINT NOT NULL)",
+            MIGRATION_TABLE
+        );
+        self.query(&sql)
+            .await
+            .with_context(|| fl!("migration-table-failed"))?;
+        Ok(())
+    }
+
+    /// whether migration tracking table exists in current database,
+    /// checked without creating it for status and dry runs
+    pub async fn migration_table_exists(&self) -> Result<bool> {
+        let sql = format!(
+            "SELECT COUNT(*) FROM {}.TABLES WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?",
+            SCHEMA_TABLE
+        );
+        let (count,): (i64,) = sqlx::query_as(&sql)
+            .bind(MIGRATION_TABLE)
+            .fetch_one(&self.pool)
+            .await
+            .with_context(|| fl!("migration-table-failed"))?;
+        Ok(count > 0)
+    }
+
+    pub async fn applied_migrations(&self) -> Result<Vec<AppliedMigration>> {
+        let sql = format!(
+            "SELECT version, name, checksum, applied_at FROM `{}` ORDER BY version",
+            MIGRATION_TABLE
+        );
+        let rows: Vec<(i64, String, String, DateTime<Utc>)> = sqlx::query_as(&sql)
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| fl!("migration-table-failed"))?;
+        Ok(rows
+            .into_iter()
+            .map(|(version, name, checksum, applied_at)| AppliedMigration {
+                version,
+                name,
+                checksum,
+                applied_at: applied_at.to_string(),
+            })
+            .collect())
+    }
+
+    /// run up migration and record it in one transaction,
+    /// note that DDL statements cause implicit commit in MySQL
+    pub async fn apply_migration(&self, migration: &Migration) -> Result<()> {
+        let start = std::time::Instant::now();
+        let mut tx = self.pool.begin().await?;
+        for sql in split_sql(&migration.up) {
+            self.query_on(&mut *tx, &sql).await.with_context(|| {
+                fl!("migration-failed", version = migration.version.to_string())
+            })?;
+        }
+        let sql = format!(
+            "INSERT INTO `{}` (version, name, checksum, execution_ms) VALUES (?, ?, ?, ?)",
+            MIGRATION_TABLE
+        );
+        sqlx::query(&sql)
+            .bind(migration.version)
+            .bind(&migration.name)
+            .bind(migration.checksum())
+            .bind(start.elapsed().as_millis() as i64)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// run down migration and remove its record in one transaction
+    pub async fn revert_migration(&self, migration: &Migration) -> Result<()> {
+        let down = migration.down.clone().unwrap_or_default();
+        let mut tx = self.pool.begin().await?;
+        for sql in split_sql(&down) {
+            self.query_on(&mut *tx, &sql).await.with_context(|| {
+                fl!("migration-failed", version = migration.version.to_string())
+            })?;
+        }
+        let sql = format!("DELETE FROM `{}` WHERE version = ?", MIGRATION_TABLE);
+        sqlx::query(&sql)
+            .bind(migration.version)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
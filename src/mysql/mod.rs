@@ -1,29 +1,92 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use crate::{config::Profile, output::QueryOutput};
-use anyhow::{Context, Result};
-use chrono::FixedOffset;
+use crate::{
+    audit,
+    config::{Profile, SafetyMode, DEFAULT_CONNECT_TIMEOUT},
+    output::QueryOutput,
+    tunnel::Tunnel,
+    utils::split_sql,
+};
+use anyhow::{anyhow, Context, Result};
+use chrono::{FixedOffset, Local};
+use either::Either;
+use futures_util::TryStreamExt;
 use sqlx::{
-    mysql::{MySqlConnectOptions, MySqlRow, MySqlSslMode},
-    MySqlPool,
+    mysql::{MySqlConnectOptions, MySqlPoolOptions, MySqlRow, MySqlSslMode},
+    Connection, Executor, MySql, MySqlConnection, MySqlPool, Row,
 };
 
 mod constants;
-pub use constants::{KEYWORDS, SCHEMA_TABLE};
+mod health;
+mod migrate;
+mod monitor;
+mod schema;
+pub use constants::{FUNCTIONS, KEYWORDS, SCHEMA_TABLE};
+pub use monitor::{kill_statement, LockWait, Process};
+pub use schema::quote_ident;
+
+/// database name of `USE db` statement
+fn used_database(sql: &str) -> Option<String> {
+    let sql = sql.trim().trim_end_matches(';').trim_end();
+    let (keyword, name) = sql.split_at(sql.find(char::is_whitespace)?);
+    if !keyword.eq_ignore_ascii_case("use") {
+        return None;
+    }
+    let name = name.trim();
+    match name.strip_prefix('`').and_then(|n| n.strip_suffix('`')) {
+        Some(quoted) => Some(quoted.replace("``", "`")),
+        None => Some(name.to_string()),
+    }
+}
 
 /// stand for mysql client server session, containing tz info etc...
 #[derive(Clone)]
 pub struct Session {
     pool: MySqlPool,
+    mode: SafetyMode,
+    /// profile and current database names recorded in audit log,
+    /// database is changed by `USE` statements
+    profile: String,
+    db: Arc<Mutex<String>>,
+    /// used to open connections outside of pool
+    options: MySqlConnectOptions,
+    init: Vec<String>,
+    /// SSH tunnel shared by all pooled connections, closed when last clone is dropped
+    _tunnel: Option<Arc<Tunnel>>,
 }
 
 impl Session {
     /// create session with profile
     pub async fn connect_with(profile: &Profile) -> Result<Self> {
-        let options = MySqlConnectOptions::new()
-            .host(&profile.host)
-            .port(profile.port)
-            .ssl_mode(MySqlSslMode::Disabled);
+        Self::open(profile, false).await
+    }
+
+    /// create session on one connection that is kept open, so that statements
+    /// of a transaction and session variables stay on it, used by shell
+    pub async fn connect_dedicated(profile: &Profile) -> Result<Self> {
+        Self::open(profile, true).await
+    }
+
+    async fn open(profile: &Profile, dedicated: bool) -> Result<Self> {
+        let tunnel = {
+            let profile = profile.clone();
+            tokio::task::spawn_blocking(move || profile.open_tunnel()).await??
+        };
+        let options = match (&tunnel, &profile.socket) {
+            (Some(tunnel), _) => MySqlConnectOptions::new()
+                .host("127.0.0.1")
+                .port(tunnel.local_port),
+            (None, Some(socket)) => MySqlConnectOptions::new().socket(socket),
+            (None, None) => MySqlConnectOptions::new()
+                .host(&profile.host)
+                .port(profile.port),
+        };
+        let options = options.ssl_mode(MySqlSslMode::Disabled);
         let options = if let Some(ref user) = profile.user {
             options.username(user)
         } else {
@@ -52,10 +115,64 @@ impl Session {
         } else {
             options
         };
-        let pool = MySqlPool::connect_with(options)
+        let options = if let Some(ref charset) = profile.charset {
+            options.charset(charset)
+        } else {
+            options
+        };
+        let options = if let Some(ref collation) = profile.collation {
+            options.collation(collation)
+        } else {
+            options
+        };
+        let timeout = profile.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT);
+        let pool_options = MySqlPoolOptions::new().connect_timeout(Duration::from_secs(timeout));
+        let pool_options = if dedicated {
+            pool_options
+                .max_connections(1)
+                .min_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+        } else if let Some(size) = profile.pool_size {
+            pool_options.max_connections(size)
+        } else {
+            pool_options
+        };
+        let pool_options = if dedicated {
+            pool_options
+        } else if let Some(idle) = profile.idle_timeout {
+            pool_options.idle_timeout(Duration::from_secs(idle))
+        } else {
+            pool_options
+        };
+        let init = profile.init_statements();
+        let pool_options = if init.is_empty() {
+            pool_options
+        } else {
+            let init = init.clone();
+            pool_options.after_connect(move |conn| {
+                let init = init.clone();
+                Box::pin(async move {
+                    for sql in init {
+                        conn.execute(sql.as_str()).await?;
+                    }
+                    Ok(())
+                })
+            })
+        };
+        let pool = pool_options
+            .connect_with(options.clone())
             .await
             .with_context(|| crate::fl!("connect-failed"))?;
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            mode: profile.mode.unwrap_or_default(),
+            profile: profile.name.clone(),
+            db: Arc::new(Mutex::new(profile.db.clone())),
+            options,
+            init,
+            _tunnel: tunnel,
+        })
     }
 
     pub async fn all_databases(&self) -> Result<HashSet<String>> {
@@ -78,17 +195,18 @@ impl Session {
         Ok(tables)
     }
 
+    /// column types of tables, such as `varchar(64)`, by table and column name
     pub async fn all_columns(
         &self,
         tables: &HashSet<String>,
-    ) -> Result<HashMap<String, HashSet<String>>> {
-        let mut columns: HashMap<String, HashSet<String>> = HashMap::new();
+    ) -> Result<HashMap<String, HashMap<String, String>>> {
+        let mut columns: HashMap<String, HashMap<String, String>> = HashMap::new();
         if tables.is_empty() {
             return Ok(columns);
         }
 
         let sql = format!(
-            "SELECT TABLE_NAME, COLUMN_NAME FROM {}.COLUMNS WHERE table_name IN ({})",
+            "SELECT TABLE_NAME, COLUMN_NAME, COLUMN_TYPE FROM {}.COLUMNS WHERE table_name IN ({})",
             SCHEMA_TABLE,
             tables
                 .iter()
@@ -96,15 +214,10 @@ impl Session {
                 .collect::<Vec<String>>()
                 .join(",")
         );
-        let query: Vec<(String, String)> = sqlx::query_as(&sql).fetch_all(&self.pool).await?;
-        query.into_iter().for_each(|(table, col)| {
-            if let Some(table) = columns.get_mut(&table) {
-                table.insert(col);
-            } else {
-                let mut entry = HashSet::new();
-                entry.insert(col);
-                columns.insert(table, entry);
-            }
+        let query: Vec<(String, String, String)> =
+            sqlx::query_as(&sql).fetch_all(&self.pool).await?;
+        query.into_iter().for_each(|(table, col, col_type)| {
+            columns.entry(table).or_default().insert(col, col_type);
         });
         Ok(columns)
     }
@@ -115,17 +228,156 @@ impl Session {
                 .fetch_one(&self.pool)
                 .await
                 .with_context(|| "tz fetch error")?;
-        Ok(FixedOffset::east(offset))
+        FixedOffset::east_opt(offset).with_context(|| "tz fetch error")
     }
 
+    /// run statement and record it in audit log
     pub async fn query(&self, to_exec: &str) -> Result<QueryOutput> {
-        let rows: Vec<MySqlRow> = sqlx::query(to_exec)
-            .fetch_all(&self.pool)
-            .await
-            .with_context(|| "")?;
+        self.query_on(&self.pool, to_exec).await
+    }
+
+    async fn query_on<'c, E>(&self, executor: E, to_exec: &str) -> Result<QueryOutput>
+    where
+        E: Executor<'c, Database = MySql>,
+    {
+        let started = Local::now();
+        let timer = Instant::now();
+        let mut rows: Vec<MySqlRow> = vec![];
+        let mut affected = 0;
+        // text protocol, prepared statements refuse CREATE PROCEDURE and the like
+        let mut stream = executor.fetch_many(to_exec);
+        let result = loop {
+            match stream.try_next().await {
+                Ok(Some(Either::Left(done))) => affected += done.rows_affected(),
+                Ok(Some(Either::Right(row))) => rows.push(row),
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+        drop(stream);
+        let db = {
+            let mut db = self.db.lock().unwrap();
+            if let (Ok(_), Some(name)) = (&result, used_database(to_exec)) {
+                *db = name;
+            }
+            db.clone()
+        };
+        audit::record(
+            &self.profile,
+            &db,
+            to_exec,
+            started,
+            timer.elapsed(),
+            rows.len() as u64,
+            affected,
+            result.as_ref().err().map(|e| e.to_string()),
+        );
+        result.with_context(|| "")?;
         Ok(QueryOutput { rows })
     }
 
+    /// run statement until `cancel` completes, then kill it with `KILL QUERY`
+    /// from a side connection
+    pub async fn query_or_cancel<F>(&self, to_exec: &str, cancel: F) -> Result<QueryOutput>
+    where
+        F: Future<Output = ()>,
+    {
+        let mut conn = self.pool.acquire().await?;
+        let (id,): (u64,) = sqlx::query_as("SELECT CONNECTION_ID()")
+            .fetch_one(&mut *conn)
+            .await?;
+        let query = self.query_on(&mut *conn, to_exec);
+        tokio::pin!(query);
+        tokio::pin!(cancel);
+        tokio::select! {
+            output = &mut query => output,
+            _ = &mut cancel => {
+                self.kill_query(id).await?;
+                // wait for the interrupted error so that the connection can be reused
+                query.await.ok();
+                Err(anyhow!(crate::fl!("query-cancelled")))
+            }
+        }
+    }
+
+    /// run statements one by one on a new connection, so that they don't hold pooled ones,
+    /// used by background jobs, output of the last one is returned,
+    /// the running one is killed like `query_or_cancel` once `cancel` completes
+    pub async fn query_detached<F>(&self, to_exec: &str, cancel: F) -> Result<QueryOutput>
+    where
+        F: Future<Output = ()>,
+    {
+        let mut conn = self.side_connection().await?;
+        for sql in self.init.iter() {
+            conn.execute(sql.as_str()).await?;
+        }
+        let (id,): (u64,) = sqlx::query_as("SELECT CONNECTION_ID()")
+            .fetch_one(&mut conn)
+            .await?;
+        let output = {
+            let query = async {
+                let mut output = QueryOutput { rows: vec![] };
+                for sql in split_sql(to_exec) {
+                    output = self.query_on(&mut conn, &sql).await?;
+                }
+                Ok(output)
+            };
+            tokio::pin!(query);
+            tokio::pin!(cancel);
+            tokio::select! {
+                output = &mut query => output,
+                _ = &mut cancel => {
+                    self.kill_query(id).await?;
+                    query.await.ok();
+                    Err(anyhow!(crate::fl!("query-cancelled")))
+                }
+            }
+        };
+        conn.close().await.ok();
+        output
+    }
+
+    /// stop statement running on connection `id` from a side connection
+    async fn kill_query(&self, id: u64) -> Result<()> {
+        let mut side = self.side_connection().await?;
+        sqlx::query(&format!("KILL QUERY {}", id))
+            .execute(&mut side)
+            .await?;
+        side.close().await.ok();
+        Ok(())
+    }
+
+    /// new connection outside of pool with the same options
+    async fn side_connection(&self) -> Result<MySqlConnection> {
+        MySqlConnection::connect_with(&self.options)
+            .await
+            .with_context(|| crate::fl!("connect-failed"))
+    }
+
+    /// safety mode of profile the session connects to
+    pub fn mode(&self) -> SafetyMode {
+        self.mode
+    }
+
+    /// rows the statement is estimated to touch, the largest `rows` of EXPLAIN output
+    pub async fn estimate_rows(&self, to_exec: &str) -> Result<Option<u64>> {
+        let plan: Vec<MySqlRow> = sqlx::query(&format!("EXPLAIN {}", to_exec))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(plan
+            .iter()
+            .filter_map(|row| {
+                row.try_get::<Option<u64>, _>("rows")
+                    .or_else(|_| {
+                        row.try_get::<Option<i64>, _>("rows")
+                            .map(|r| r.map(|r| r as u64))
+                    })
+                    .ok()
+                    .flatten()
+            })
+            .max())
+    }
+
     pub async fn close(&self) {
         self.pool.close().await
     }
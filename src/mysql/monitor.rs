@@ -0,0 +1,129 @@
+use super::{Session, SCHEMA_TABLE};
+use crate::{fl, guard};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+
+/// an active session in process list
+#[derive(Debug, Clone)]
+pub struct Process {
+    pub id: u64,
+    pub user: String,
+    pub host: String,
+    pub db: Option<String>,
+    pub command: String,
+    pub time: i64,
+    pub state: Option<String>,
+    pub info: Option<String>,
+}
+
+/// an InnoDB lock wait and its blocker
+#[derive(Debug, Clone)]
+pub struct LockWait {
+    pub waiting_pid: u64,
+    pub waiting_query: Option<String>,
+    pub blocking_pid: u64,
+    pub blocking_query: Option<String>,
+    pub wait_secs: i64,
+}
+
+type ProcessRow = (
+    u64,
+    String,
+    String,
+    Option<String>,
+    String,
+    i64,
+    Option<String>,
+    Option<String>,
+);
+type LockWaitRow = (u64, Option<String>, u64, Option<String>, i64);
+
+impl Session {
+    /// sessions which are not sleeping, longest running first
+    pub async fn processes(&self) -> Result<Vec<Process>> {
+        let sql = format!(
+            "SELECT CAST(ID AS UNSIGNED), USER, HOST, DB, COMMAND, CAST(TIME AS SIGNED), STATE, \
+             LEFT(INFO, 512) FROM {}.PROCESSLIST \
+             WHERE COMMAND <> 'Sleep' AND ID <> CONNECTION_ID() ORDER BY TIME DESC",
+            SCHEMA_TABLE
+        );
+        let rows: Vec<ProcessRow> = sqlx::query_as(&sql)
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| fl!("monitor-query-failed"))?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, user, host, db, command, time, state, info)| Process {
+                id,
+                user,
+                host,
+                db,
+                command,
+                time,
+                state,
+                info,
+            })
+            .collect())
+    }
+
+    /// lock waits from `sys.innodb_lock_waits`, requires sys schema
+    pub async fn lock_waits(&self) -> Result<Vec<LockWait>> {
+        let sql = "SELECT CAST(waiting_pid AS UNSIGNED), LEFT(waiting_query, 256), \
+                   CAST(blocking_pid AS UNSIGNED), LEFT(blocking_query, 256), \
+                   CAST(wait_age_secs AS SIGNED) \
+                   FROM sys.innodb_lock_waits ORDER BY wait_age_secs DESC";
+        let rows: Vec<LockWaitRow> = sqlx::query_as(sql)
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| fl!("monitor-query-failed"))?;
+        Ok(rows
+            .into_iter()
+            .map(
+                |(waiting_pid, waiting_query, blocking_pid, blocking_query, wait_secs)| LockWait {
+                    waiting_pid,
+                    waiting_query,
+                    blocking_pid,
+                    blocking_query,
+                    wait_secs,
+                },
+            )
+            .collect())
+    }
+
+    /// `SHOW GLOBAL STATUS` as name -> value
+    pub async fn global_status(&self) -> Result<HashMap<String, String>> {
+        let rows: Vec<(String, String)> = sqlx::query_as("SHOW GLOBAL STATUS")
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| fl!("monitor-query-failed"))?;
+        Ok(rows.into_iter().collect())
+    }
+
+    /// `SHOW GLOBAL VARIABLES` as name -> value
+    pub async fn global_variables(&self) -> Result<HashMap<String, String>> {
+        let rows: Vec<(String, String)> = sqlx::query_as("SHOW GLOBAL VARIABLES")
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| fl!("monitor-query-failed"))?;
+        Ok(rows.into_iter().collect())
+    }
+
+    /// kill query or whole connection of process id, refused on read-only profiles
+    pub async fn kill(&self, id: u64, query_only: bool) -> Result<()> {
+        let sql = kill_statement(id, query_only);
+        guard::precheck(self.mode, &sql, true)?;
+        self.query(&sql)
+            .await
+            .with_context(|| fl!("kill-failed", id = id.to_string()))?;
+        Ok(())
+    }
+}
+
+/// `KILL QUERY <id>` or `KILL <id>`
+pub fn kill_statement(id: u64, query_only: bool) -> String {
+    if query_only {
+        format!("KILL QUERY {}", id)
+    } else {
+        format!("KILL {}", id)
+    }
+}
@@ -0,0 +1,201 @@
+use super::{Session, SCHEMA_TABLE};
+use crate::{
+    codegen::ColumnMeta,
+    erd::{Erd, ErdColumn, ErdRelation, ErdTable},
+    output::QueryOutput,
+};
+use anyhow::{Context, Result};
+use sqlx::{mysql::MySqlRow, Column, Executor, TypeInfo};
+use std::collections::HashSet;
+
+/// quote identifier with backtick, inner backtick is escaped
+pub fn quote_ident(ident: &str) -> String {
+    format!("`{}`", ident.replace('`', "``"))
+}
+
+impl Session {
+    /// run sql with positional string parameters
+    async fn query_with(&self, sql: &str, params: &[&str]) -> Result<QueryOutput> {
+        let mut query = sqlx::query(sql);
+        for param in params {
+            query = query.bind(*param);
+        }
+        let rows: Vec<MySqlRow> = query
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| crate::fl!("schema-query-failed"))?;
+        Ok(QueryOutput { rows })
+    }
+
+    /// tables of current database with engine, row estimate and size in MB
+    pub async fn table_status(&self, pattern: Option<&str>) -> Result<QueryOutput> {
+        let sql = format!(
+            "SELECT TABLE_NAME AS `table`, ENGINE AS `engine`, TABLE_ROWS AS `rows`, \
+             ROUND(DATA_LENGTH / 1048576, 2) AS `data_mb`, \
+             ROUND(INDEX_LENGTH / 1048576, 2) AS `index_mb`, \
+             TABLE_COMMENT AS `comment` \
+             FROM {}.TABLES WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME LIKE ? \
+             ORDER BY TABLE_NAME",
+            SCHEMA_TABLE
+        );
+        self.query_with(&sql, &[pattern.unwrap_or("%")]).await
+    }
+
+    /// columns of a table, in ordinal order
+    pub async fn describe(&self, table: &str) -> Result<QueryOutput> {
+        let sql = format!(
+            "SELECT COLUMN_NAME AS `field`, COLUMN_TYPE AS `type`, IS_NULLABLE AS `null`, \
+             COLUMN_KEY AS `key`, COLUMN_DEFAULT AS `default`, EXTRA AS `extra`, \
+             COLUMN_COMMENT AS `comment` \
+             FROM {}.COLUMNS WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? \
+             ORDER BY ORDINAL_POSITION",
+            SCHEMA_TABLE
+        );
+        self.query_with(&sql, &[table]).await
+    }
+
+    /// names of columns in any index of current database, in lower case
+    pub async fn indexed_columns(&self) -> Result<HashSet<String>> {
+        let sql = format!(
+            "SELECT DISTINCT COLUMN_NAME FROM {}.STATISTICS WHERE TABLE_SCHEMA = DATABASE()",
+            SCHEMA_TABLE
+        );
+        let rows: Vec<(String,)> = sqlx::query_as(&sql)
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| crate::fl!("schema-query-failed"))?;
+        Ok(rows
+            .into_iter()
+            .map(|(column,)| column.to_ascii_lowercase())
+            .collect())
+    }
+
+    /// indexes of a table, one row per index with columns joined in order
+    pub async fn indexes(&self, table: &str) -> Result<QueryOutput> {
+        let sql = format!(
+            "SELECT INDEX_NAME AS `index`, IF(NON_UNIQUE = 0, 'YES', 'NO') AS `unique`, \
+             GROUP_CONCAT(COLUMN_NAME ORDER BY SEQ_IN_INDEX SEPARATOR ', ') AS `columns`, \
+             INDEX_TYPE AS `type`, MAX(CARDINALITY) AS `cardinality` \
+             FROM {}.STATISTICS WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? \
+             GROUP BY INDEX_NAME, NON_UNIQUE, INDEX_TYPE \
+             ORDER BY INDEX_NAME = 'PRIMARY' DESC, INDEX_NAME",
+            SCHEMA_TABLE
+        );
+        self.query_with(&sql, &[table]).await
+    }
+
+    /// outbound (table references others) and inbound (others reference table) foreign keys
+    pub async fn foreign_keys(&self, table: &str) -> Result<QueryOutput> {
+        let sql = format!(
+            "SELECT IF(k.TABLE_SCHEMA = DATABASE() AND k.TABLE_NAME = ?, 'outbound', 'inbound') AS `direction`, \
+             k.CONSTRAINT_NAME AS `constraint`, k.TABLE_NAME AS `table`, k.COLUMN_NAME AS `column`, \
+             k.REFERENCED_TABLE_NAME AS `ref_table`, k.REFERENCED_COLUMN_NAME AS `ref_column`, \
+             r.UPDATE_RULE AS `on_update`, r.DELETE_RULE AS `on_delete` \
+             FROM {schema}.KEY_COLUMN_USAGE k JOIN {schema}.REFERENTIAL_CONSTRAINTS r \
+             ON r.CONSTRAINT_SCHEMA = k.CONSTRAINT_SCHEMA AND r.CONSTRAINT_NAME = k.CONSTRAINT_NAME \
+             WHERE k.REFERENCED_TABLE_NAME IS NOT NULL \
+             AND ((k.TABLE_SCHEMA = DATABASE() AND k.TABLE_NAME = ?) \
+             OR (k.REFERENCED_TABLE_SCHEMA = DATABASE() AND k.REFERENCED_TABLE_NAME = ?)) \
+             ORDER BY `direction` DESC, k.CONSTRAINT_NAME, k.ORDINAL_POSITION",
+            schema = SCHEMA_TABLE
+        );
+        self.query_with(&sql, &[table, table, table]).await
+    }
+
+    /// `CREATE TABLE` (or `CREATE VIEW`) statement of a table
+    pub async fn ddl(&self, table: &str) -> Result<String> {
+        let sql = format!("SHOW CREATE TABLE {}", quote_ident(table));
+        let (_, ddl): (String, String) = sqlx::query_as(&sql)
+            .fetch_one(&self.pool)
+            .await
+            .with_context(|| crate::fl!("schema-query-failed"))?;
+        Ok(ddl)
+    }
+
+    /// name, type and nullability of result columns of sql, without executing it
+    pub async fn column_types(&self, sql: &str) -> Result<Vec<ColumnMeta>> {
+        let describe = (&self.pool)
+            .describe(sql)
+            .await
+            .with_context(|| crate::fl!("schema-query-failed"))?;
+        let columns = describe
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(idx, col)| ColumnMeta {
+                name: col.name().to_string(),
+                type_name: col.type_info().name().to_string(),
+                nullable: describe.nullable(idx).unwrap_or(true),
+            })
+            .collect();
+        Ok(columns)
+    }
+
+    /// load tables, columns and foreign keys of current database for ER diagram
+    pub async fn erd(&self, pattern: Option<&str>) -> Result<Erd> {
+        let pattern = pattern.unwrap_or("%");
+        let sql = format!(
+            "SELECT c.TABLE_NAME, c.COLUMN_NAME, c.DATA_TYPE, c.COLUMN_KEY, c.IS_NULLABLE \
+             FROM {schema}.COLUMNS c JOIN {schema}.TABLES t \
+             ON t.TABLE_SCHEMA = c.TABLE_SCHEMA AND t.TABLE_NAME = c.TABLE_NAME \
+             WHERE c.TABLE_SCHEMA = DATABASE() AND t.TABLE_TYPE = 'BASE TABLE' \
+             AND c.TABLE_NAME LIKE ? \
+             ORDER BY c.TABLE_NAME, c.ORDINAL_POSITION",
+            schema = SCHEMA_TABLE
+        );
+        let columns: Vec<(String, String, String, String, String)> = sqlx::query_as(&sql)
+            .bind(pattern)
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| crate::fl!("schema-query-failed"))?;
+        let mut erd = Erd::default();
+        for (table, name, data_type, key, nullable) in columns {
+            let column = ErdColumn {
+                name,
+                data_type,
+                primary: key == "PRI",
+                nullable: nullable == "YES",
+            };
+            match erd.tables.last_mut() {
+                Some(last) if last.name == table => last.columns.push(column),
+                _ => erd.tables.push(ErdTable {
+                    name: table,
+                    columns: vec![column],
+                }),
+            }
+        }
+
+        let sql = format!(
+            "SELECT CONSTRAINT_NAME, TABLE_NAME, COLUMN_NAME, REFERENCED_TABLE_NAME, \
+             REFERENCED_COLUMN_NAME FROM {}.KEY_COLUMN_USAGE \
+             WHERE TABLE_SCHEMA = DATABASE() AND REFERENCED_TABLE_SCHEMA = DATABASE() \
+             AND REFERENCED_TABLE_NAME IS NOT NULL \
+             ORDER BY TABLE_NAME, CONSTRAINT_NAME, ORDINAL_POSITION",
+            SCHEMA_TABLE
+        );
+        let keys: Vec<(String, String, String, String, String)> = sqlx::query_as(&sql)
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| crate::fl!("schema-query-failed"))?;
+        let selected: HashSet<String> = erd.tables.iter().map(|t| t.name.clone()).collect();
+        for (constraint, table, column, ref_table, ref_column) in keys {
+            if !selected.contains(&table) || !selected.contains(&ref_table) {
+                continue;
+            }
+            match erd.relations.last_mut() {
+                Some(last) if last.table == table && last.constraint == constraint => {
+                    last.columns.push(column);
+                    last.ref_columns.push(ref_column);
+                }
+                _ => erd.relations.push(ErdRelation {
+                    constraint,
+                    table,
+                    columns: vec![column],
+                    ref_table,
+                    ref_columns: vec![ref_column],
+                }),
+            }
+        }
+        Ok(erd)
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::{anyhow, Context, Result};
 use bigdecimal::BigDecimal;
 use chrono::{DateTime, Utc};
+use comfy_table::{Cell, Table};
 use serde::{
     ser::{SerializeMap, SerializeSeq},
     Serialize,
@@ -10,15 +11,16 @@ use sqlx::{
     types::time::{Date, Time},
     Column, Row, TypeInfo, Value, ValueRef,
 };
-use std::{str::FromStr, vec};
+use std::{io::Write, path::Path, str::FromStr, vec};
 
-use crate::{config::Config, fl};
+use crate::{config::Config, fl, theme};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Default)]
 pub enum Format {
     #[serde(rename = "csv")]
     Csv,
     #[serde(rename = "json")]
+    #[default]
     Json,
     #[serde(rename = "yaml")]
     Yaml,
@@ -28,12 +30,6 @@ pub enum Format {
     Pickle,
 }
 
-impl Default for Format {
-    fn default() -> Self {
-        Format::Json
-    }
-}
-
 impl FromStr for Format {
     type Err = anyhow::Error;
 
@@ -54,6 +50,51 @@ impl FromStr for Format {
         }
     }
 }
+/// rows printed as table or written in any format, the serialized value is written
+/// as json, yaml, toml and pickle
+pub trait Records: Serialize {
+    /// csv text with a header line
+    fn to_csv(&self) -> Result<String>;
+
+    fn print_table(&self, config: &Config);
+}
+
+impl Format {
+    /// serialized records, text formats end with a new line
+    pub fn to_bytes<R: Records>(&self, records: &R) -> Result<Vec<u8>> {
+        let text =
+            match self {
+                Format::Csv => records.to_csv()?,
+                Format::Json => serde_json::to_string(records)
+                    .with_context(|| fl!("serialize-output-failed"))?,
+                Format::Yaml => serde_yaml::to_string(records)
+                    .with_context(|| fl!("serialize-output-failed"))?,
+                Format::Toml => toml::to_string_pretty(records)
+                    .with_context(|| fl!("serialize-output-failed"))?,
+                Format::Pickle => {
+                    let mut out = vec![];
+                    serde_pickle::to_writer(&mut out, records, false)
+                        .with_context(|| fl!("serialize-output-failed"))?;
+                    return Ok(out);
+                }
+            };
+        Ok(format!("{}\n", text.trim_end_matches('\n')).into_bytes())
+    }
+
+    /// format by file extension, `None` for unknown ones which are written as table
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "pickle" | "pkl" => Some(Format::Pickle),
+            _ => None,
+        }
+    }
+}
+
 pub struct QueryOutput {
     pub rows: Vec<MySqlRow>,
 }
@@ -64,7 +105,6 @@ pub struct DCliColumn<'a> {
 
 pub struct QueryOutputMapSer<'a>(pub &'a QueryOutput);
 struct DcliRowMapSer<'a>(&'a MySqlRow);
-struct QueryOutputListSer<'a>(&'a QueryOutput);
 struct DcliRowListSer<'a>(&'a MySqlRow);
 
 impl<'a> Serialize for QueryOutputMapSer<'a> {
@@ -96,19 +136,6 @@ impl<'a> Serialize for DcliRowMapSer<'a> {
     }
 }
 
-impl<'a> Serialize for QueryOutputListSer<'a> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let mut seq = serializer.serialize_seq(Some(self.0.rows.len()))?;
-        for row in self.0.rows.iter().map(DcliRowListSer) {
-            seq.serialize_element(&row)?;
-        }
-        seq.end()
-    }
-}
-
 impl<'a> Serialize for DcliRowListSer<'a> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -201,7 +228,7 @@ impl<'a> Serialize for DCliColumn<'a> {
                     let b64_str = val.try_decode::<Vec<u8>>().map(base64::encode).unwrap();
                     serializer.serialize_str(&b64_str)
                 }
-                t => unreachable!(t),
+                t => unreachable!("{}", t),
             }
         }
     }
@@ -247,12 +274,27 @@ impl QueryOutput {
                 "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BINARY" | "VARBINARY" => {
                     val.try_decode::<Vec<u8>>().map(base64::encode)
                 }
-                t => unreachable!(t),
+                t => unreachable!("{}", t),
             }
         };
         val.unwrap()
     }
 
+    fn is_null(row: &MySqlRow, col: &MySqlColumn) -> bool {
+        row.try_get_raw(col.ordinal()).unwrap().is_null()
+    }
+
+    /// table cell of value, NULL is shown as `NULL` in theme color if styled
+    fn value_cell(row: &MySqlRow, col: &MySqlColumn, styled: bool) -> Cell {
+        if !QueryOutput::is_null(row, col) {
+            Cell::new(QueryOutput::convert_col(row, col))
+        } else if styled {
+            theme::current().null.cell("NULL")
+        } else {
+            Cell::new("NULL")
+        }
+    }
+
     fn vertical_print_table(&self, config: &Config) {
         if self.rows.is_empty() {
             return;
@@ -261,14 +303,15 @@ impl QueryOutput {
         if keys.is_empty() {
             return;
         }
+        let theme = theme::current();
         for (row_idx, row) in self.rows.iter().enumerate() {
             let mut table = config.new_table();
             table.load_preset("        :          ");
 
             for (idx, col) in row.columns().iter().enumerate() {
-                table.add_row(&[
-                    keys.get(idx).unwrap().name(),
-                    &QueryOutput::convert_col(row, col),
+                table.add_row(vec![
+                    theme.header.cell(keys.get(idx).unwrap().name()),
+                    QueryOutput::value_cell(row, col, true),
                 ]);
             }
             println!(
@@ -279,25 +322,76 @@ impl QueryOutput {
         }
     }
 
-    fn horizontal_print_table(&self, config: &Config) {
-        if self.rows.is_empty() {
-            return;
-        }
-        let header = self.rows.first().unwrap();
+    /// column names, empty if no rows returned
+    pub fn header(&self) -> Vec<String> {
+        self.rows
+            .first()
+            .map(|row| row.columns().iter().map(|c| c.name().to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// all values as json values in column order, typed like exported json
+    pub fn json_values(&self) -> Result<Vec<Vec<serde_json::Value>>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                let value = serde_json::to_value(DcliRowListSer(row))
+                    .with_context(|| fl!("serialize-output-failed"))?;
+                serde_json::from_value(value).with_context(|| fl!("serialize-output-failed"))
+            })
+            .collect()
+    }
+
+    /// all values as display string, NULL is converted to empty string
+    pub fn values(&self) -> Vec<Vec<String>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                row.columns()
+                    .iter()
+                    .map(|col| QueryOutput::convert_col(row, col))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// table of all rows, header and NULL are colored by theme if styled
+    fn horizontal_table(&self, config: &Config, styled: bool) -> Option<Table> {
+        let header = self.rows.first()?;
         let header_cols = header.columns();
         if header_cols.is_empty() {
-            return;
+            return None;
         }
+        let theme = theme::current();
         let mut table = config.new_table();
-        table.set_header(header_cols.iter().map(|col| col.name()));
+        table.set_header(header_cols.iter().map(|col| {
+            if styled {
+                theme.header.cell(col.name())
+            } else {
+                Cell::new(col.name())
+            }
+        }));
         self.rows.iter().for_each(|row| {
             table.add_row(
                 row.columns()
                     .iter()
-                    .map(|col| QueryOutput::convert_col(row, col)),
+                    .map(|col| QueryOutput::value_cell(row, col, styled)),
             );
         });
-        println!("{}", table)
+        Some(table)
+    }
+
+    fn horizontal_print_table(&self, config: &Config) {
+        if let Some(table) = self.horizontal_table(config, true) {
+            println!("{}", table)
+        }
+    }
+
+    /// table text as printed without colors, empty if no rows returned
+    pub fn to_table_string(&self, config: &Config) -> String {
+        self.horizontal_table(config, false)
+            .map(|table| format!("{}\n", table))
+            .unwrap_or_default()
     }
 
     pub fn to_print_table(&self, config: &Config, vertical: bool) {
@@ -308,7 +402,28 @@ impl QueryOutput {
         }
     }
 
-    pub fn to_csv(&self) -> Result<String> {
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(&QueryOutputMapSer(self))
+            .with_context(|| fl!("serialize-output-failed"))
+    }
+
+    pub fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(&QueryOutputMapSer(self))
+            .with_context(|| fl!("serialize-output-failed"))
+    }
+}
+
+impl Serialize for QueryOutput {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        QueryOutputMapSer(self).serialize(serializer)
+    }
+}
+
+impl Records for QueryOutput {
+    fn to_csv(&self) -> Result<String> {
         if self.rows.is_empty() {
             return Ok(String::new());
         }
@@ -334,25 +449,24 @@ impl QueryOutput {
         Ok(String::from_utf8_lossy(&out).to_string())
     }
 
-    pub fn to_json(&self) -> Result<String> {
-        serde_json::to_string(&QueryOutputMapSer(self))
-            .with_context(|| fl!("serialize-output-failed"))
-    }
-
-    pub fn to_yaml(&self) -> Result<String> {
-        serde_yaml::to_string(&QueryOutputMapSer(self))
-            .with_context(|| fl!("serialize-output-failed"))
-    }
-
-    pub fn to_toml(&self) -> Result<String> {
-        toml::to_string_pretty(&QueryOutputMapSer(self))
-            .with_context(|| fl!("serialize-output-failed"))
+    fn print_table(&self, config: &Config) {
+        self.to_print_table(config, false);
     }
+}
 
-    pub fn to_pickle(&self) -> Result<Vec<u8>> {
-        let mut out = vec![];
-        serde_pickle::to_writer(&mut out, &QueryOutputMapSer(self), false)
-            .with_context(|| fl!("serialize-output-failed"))?;
-        Ok(out)
+/// print records as table, or write them to stdout in format
+pub fn print_records<R: Records>(
+    config: &Config,
+    records: &R,
+    format: Option<&Format>,
+) -> Result<()> {
+    match format {
+        None => records.print_table(config),
+        Some(format) => {
+            let mut stdout = std::io::stdout();
+            stdout.write_all(&format.to_bytes(records)?)?;
+            stdout.flush()?;
+        }
     }
+    Ok(())
 }
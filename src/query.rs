@@ -20,42 +20,42 @@ pub struct Paging {
 
 impl Paging {
     pub fn to_params() -> Vec<ReferenceOr<Parameter>> {
-        let mut params = vec![];
-        params.push(ReferenceOr::Item(Parameter::Query {
-            parameter_data: ParameterData {
-                name: "limit".to_string(),
-                description: Some("max row limit, or page size".to_string()),
-                required: false,
-                deprecated: None,
-                format: ParameterSchemaOrContent::Schema(ReferenceOr::Item(Schema {
-                    schema_data: SchemaData::default(),
-                    schema_kind: SchemaKind::Type(Type::Integer(IntegerType::default())),
-                })),
-                example: None,
-                examples: Default::default(),
-            },
-            allow_reserved: false,
-            style: Default::default(),
-            allow_empty_value: None,
-        }));
-        params.push(ReferenceOr::Item(Parameter::Query {
-            parameter_data: ParameterData {
-                name: "offset".to_string(),
-                description: Some("query offset".to_string()),
-                required: false,
-                deprecated: None,
-                format: ParameterSchemaOrContent::Schema(ReferenceOr::Item(Schema {
-                    schema_data: SchemaData::default(),
-                    schema_kind: SchemaKind::Type(Type::Integer(IntegerType::default())),
-                })),
-                example: None,
-                examples: Default::default(),
-            },
-            allow_reserved: false,
-            style: Default::default(),
-            allow_empty_value: None,
-        }));
-        params
+        vec![
+            ReferenceOr::Item(Parameter::Query {
+                parameter_data: ParameterData {
+                    name: "limit".to_string(),
+                    description: Some("max row limit, or page size".to_string()),
+                    required: false,
+                    deprecated: None,
+                    format: ParameterSchemaOrContent::Schema(ReferenceOr::Item(Schema {
+                        schema_data: SchemaData::default(),
+                        schema_kind: SchemaKind::Type(Type::Integer(IntegerType::default())),
+                    })),
+                    example: None,
+                    examples: Default::default(),
+                },
+                allow_reserved: false,
+                style: Default::default(),
+                allow_empty_value: None,
+            }),
+            ReferenceOr::Item(Parameter::Query {
+                parameter_data: ParameterData {
+                    name: "offset".to_string(),
+                    description: Some("query offset".to_string()),
+                    required: false,
+                    deprecated: None,
+                    format: ParameterSchemaOrContent::Schema(ReferenceOr::Item(Schema {
+                        schema_data: SchemaData::default(),
+                        schema_kind: SchemaKind::Type(Type::Integer(IntegerType::default())),
+                    })),
+                    example: None,
+                    examples: Default::default(),
+                },
+                allow_reserved: false,
+                style: Default::default(),
+                allow_empty_value: None,
+            }),
+        ]
     }
 }
 
@@ -70,9 +70,11 @@ pub struct Query {
 
 impl Query {
     pub fn open_api(&self) -> PathItem {
-        let mut get = Operation::default();
-        get.operation_id = Some(self.url.clone());
-        get.summary = self.description.clone();
+        let mut get = Operation {
+            operation_id: Some(self.url.clone()),
+            summary: self.description.clone(),
+            ..Default::default()
+        };
         let mut content = IndexMap::new();
         content.insert("application/json".to_string(), MediaType::default());
         if self.paging != Some(false) {
@@ -82,7 +84,7 @@ impl Query {
             default: Some(ReferenceOr::Item(Response {
                 description: "OK".to_string(),
                 headers: IndexMap::new(),
-                content: content,
+                content,
                 links: IndexMap::new(),
             })),
             responses: IndexMap::new(),
@@ -176,7 +178,7 @@ impl QueryPlan {
         let to_match = full_path.as_str()[(self.prefix.len() + 2)..].trim_end_matches('/');
         let query = self.queries.iter().find(|q| q.url == to_match).unwrap();
         let sess = sessions.get(&query.profile).unwrap();
-        Ok(sess.query(&query.with_paging(paging)).await?)
+        sess.query(&query.with_paging(paging)).await
     }
 
     pub fn with_meta(&self) -> Self {
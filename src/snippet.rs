@@ -0,0 +1,101 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use crate::{config::Config, fl, template, utils::read_file};
+
+/// a saved query, `${name}` placeholders are filled when it is called
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub sql: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// shared snippet file, such as a team file kept in git
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SnippetFile {
+    #[serde(default)]
+    pub snippets: HashMap<String, Snippet>,
+}
+
+/// where a snippet is defined
+#[derive(Debug, Clone)]
+pub enum SnippetSource {
+    Local,
+    File(PathBuf),
+}
+
+impl std::fmt::Display for SnippetSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnippetSource::Local => write!(f, "local"),
+            SnippetSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl SnippetFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = read_file(&path.to_string_lossy())?;
+        toml::from_str(&content).with_context(|| {
+            fl!(
+                "snippet-file-invalid",
+                file = path.to_string_lossy().to_string()
+            )
+        })
+    }
+}
+
+impl Snippet {
+    /// placeholder names in order of first appearance
+    pub fn params(&self) -> Vec<String> {
+        template::params(&self.sql)
+    }
+}
+
+/// all snippets, local ones override those in shared files
+pub fn all_snippets(config: &Config) -> BTreeMap<String, (Snippet, SnippetSource)> {
+    let mut all = BTreeMap::new();
+    for path in &config.snippet_files {
+        match SnippetFile::load(path) {
+            Ok(file) => {
+                for (name, snippet) in file.snippets {
+                    all.insert(name, (snippet, SnippetSource::File(path.clone())));
+                }
+            }
+            Err(e) => log::warn!("skip snippet file {:?}: {:#}", path, e),
+        }
+    }
+    for (name, snippet) in &config.snippets {
+        all.insert(name.clone(), (snippet.clone(), SnippetSource::Local));
+    }
+    all
+}
+
+pub fn find_snippet(config: &Config, name: &str) -> Result<Snippet> {
+    all_snippets(config)
+        .remove(name)
+        .map(|(snippet, _)| snippet)
+        .ok_or_else(|| anyhow!(fl!("snippet-not-found", name = name)))
+}
+
+/// resolve snippet call such as `slow_orders days=7` to SQL,
+/// arguments take precedence over variables
+pub fn call(
+    config: &Config,
+    name: &str,
+    args: &[String],
+    vars: &HashMap<String, String>,
+) -> Result<String> {
+    let snippet = find_snippet(config, name)?;
+    let mut vars = vars.clone();
+    for arg in args {
+        let (key, value) = template::parse_var(arg)?;
+        vars.insert(key, value);
+    }
+    template::render(&snippet.sql, &vars)
+}
@@ -0,0 +1,242 @@
+use anyhow::{anyhow, Result};
+use crossterm::tty::IsTty;
+use once_cell::sync::OnceCell;
+use std::{collections::HashMap, io::Write};
+
+use crate::{config::Config, fl, mysql::quote_ident};
+
+static VARS_IN_QUOTES: OnceCell<bool> = OnceCell::new();
+
+/// read template settings of config, called once on start
+pub fn init(config: &Config) {
+    VARS_IN_QUOTES.set(config.vars_in_quotes).ok();
+}
+
+/// placeholders inside quoted strings and identifiers are substituted only if enabled
+fn vars_in_quotes() -> bool {
+    VARS_IN_QUOTES.get().copied().unwrap_or(false)
+}
+
+/// how a value is written into SQL text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quote {
+    /// `${name}`, string literal, numbers are written as is
+    Literal,
+    /// `${name:ident}`, backtick quoted identifier
+    Ident,
+    /// `${name:raw}`, written as is
+    Raw,
+    /// placeholder inside a quoted string or identifier, only escape for that quote,
+    /// found only when `vars_in_quotes` is enabled
+    Inside(char),
+}
+
+/// a `${...}` placeholder found in SQL text
+#[derive(Debug, Clone)]
+pub struct Placeholder {
+    /// byte range of whole placeholder in text
+    pub start: usize,
+    pub end: usize,
+    pub name: String,
+    pub quote: Quote,
+    /// `${env:NAME}`, value is read from environment variable
+    pub env: bool,
+}
+
+fn is_var_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// parse `key=value` from command line
+pub fn parse_var(s: &str) -> Result<(String, String)> {
+    match s.split_once('=') {
+        Some((key, value)) if is_var_name(key) => Ok((key.to_string(), value.to_string())),
+        _ => Err(anyhow!(fl!("invalid-value", val = s))),
+    }
+}
+
+/// position of first `pat` in `bytes` from `from`
+fn find(bytes: &[u8], from: usize, pat: &[u8]) -> Option<usize> {
+    bytes
+        .get(from..)?
+        .windows(pat.len())
+        .position(|w| w == pat)
+        .map(|n| from + n)
+}
+
+/// find placeholders outside of comments, remember quote context of each one,
+/// placeholders in quotes are skipped unless `vars_in_quotes` is enabled
+pub fn placeholders(sql: &str) -> Vec<Placeholder> {
+    let bytes = sql.as_bytes();
+    let mut found = vec![];
+    let mut quote: Option<u8> = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        match quote {
+            Some(q) => {
+                if c == b'\\' && q != b'`' {
+                    i += 2;
+                    continue;
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None => {
+                if c == b'\'' || c == b'"' || c == b'`' {
+                    quote = Some(c);
+                } else if c == b'#' || bytes[i..].starts_with(b"-- ") {
+                    i = find(bytes, i, b"\n").unwrap_or(bytes.len());
+                    continue;
+                } else if bytes[i..].starts_with(b"/*") {
+                    i = find(bytes, i + 2, b"*/").map_or(bytes.len(), |n| n + 2);
+                    continue;
+                }
+            }
+        }
+        if bytes[i..].starts_with(b"${") {
+            if let Some(close) = find(bytes, i + 2, b"}") {
+                // `$` and `}` are ascii, so both ends are char boundaries
+                let inner = &sql[i + 2..close];
+                let (env, inner) = match inner.strip_prefix("env:") {
+                    Some(rest) => (true, rest),
+                    None => (false, inner),
+                };
+                let (name, modifier) = match inner.split_once(':') {
+                    Some((name, modifier)) => (name, Some(modifier)),
+                    None => (inner, None),
+                };
+                let kind = match (quote, modifier) {
+                    (Some(q), None) if vars_in_quotes() => Some(Quote::Inside(q as char)),
+                    (None, None) => Some(Quote::Literal),
+                    (None, Some("ident")) => Some(Quote::Ident),
+                    (None, Some("raw")) => Some(Quote::Raw),
+                    _ => None,
+                };
+                if let (true, Some(kind)) = (is_var_name(name), kind) {
+                    let end = close + 1;
+                    found.push(Placeholder {
+                        start: i,
+                        end,
+                        name: name.to_string(),
+                        quote: kind,
+                        env,
+                    });
+                    i = end;
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+    found
+}
+
+/// unique placeholder names in order of first appearance, environment variables excluded
+pub fn params(sql: &str) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for p in placeholders(sql) {
+        if !p.env && !names.contains(&p.name) {
+            names.push(p.name);
+        }
+    }
+    names
+}
+
+/// string literal, numbers are kept as is
+pub fn quote_literal(value: &str) -> String {
+    let numeric = value
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'));
+    if numeric && value.parse::<f64>().is_ok() {
+        value.to_string()
+    } else {
+        format!("'{}'", escape(value, '\''))
+    }
+}
+
+/// quotes are doubled rather than escaped with `\`, so that the value can't end
+/// the literal when `sql_mode` has NO_BACKSLASH_ESCAPES
+fn escape(value: &str, quote: char) -> String {
+    if quote == '`' {
+        value.replace('`', "``")
+    } else {
+        value
+            .replace('\\', "\\\\")
+            .replace(quote, &format!("{}{}", quote, quote))
+    }
+}
+
+pub fn quote_value(value: &str, quote: Quote) -> String {
+    match quote {
+        Quote::Literal => quote_literal(value),
+        Quote::Ident => quote_ident(value),
+        Quote::Raw => value.to_string(),
+        Quote::Inside(q) => escape(value, q),
+    }
+}
+
+/// ask user for value of a variable on terminal, fail if input is not a terminal
+pub fn prompt(name: &str) -> Result<String> {
+    if !std::io::stdin().is_tty() {
+        return Err(anyhow!(fl!("var-missing", name = name)));
+    }
+    print!("{}: ", name);
+    std::io::stdout().flush()?;
+    let mut value = String::new();
+    std::io::stdin().read_line(&mut value)?;
+    let value = value.trim_end_matches(['\r', '\n']);
+    if value.is_empty() {
+        Err(anyhow!(fl!("var-missing", name = name)))
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+/// substitute placeholders with variables and `${env:NAME}` with environment variables,
+/// prompt for variables still missing
+pub fn render(sql: &str, vars: &HashMap<String, String>) -> Result<String> {
+    let found = placeholders(sql);
+    if found.is_empty() {
+        return Ok(sql.to_string());
+    }
+    let mut resolved: HashMap<(bool, String), String> = HashMap::new();
+    let mut out = String::with_capacity(sql.len());
+    let mut last = 0;
+    for p in found {
+        let key = (p.env, p.name.clone());
+        if !resolved.contains_key(&key) {
+            let value = if p.env {
+                std::env::var(&p.name)
+                    .map_err(|_| anyhow!(fl!("env-not-set", name = p.name.clone())))?
+            } else {
+                match vars.get(&p.name) {
+                    Some(value) => value.clone(),
+                    None => prompt(&p.name)?,
+                }
+            };
+            resolved.insert(key.clone(), value);
+        }
+        out.push_str(&sql[last..p.start]);
+        out.push_str(&quote_value(&resolved[&key], p.quote));
+        last = p.end;
+    }
+    out.push_str(&sql[last..]);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_quote_and_backslash() {
+        let value = "it's \\' or 1=1 -- ";
+        assert_eq!(quote_literal(value), "'it''s \\\\'' or 1=1 -- '");
+        assert_eq!(
+            quote_value(value, Quote::Inside('"')),
+            "it's \\\\' or 1=1 -- "
+        );
+        assert_eq!(quote_value("a`b", Quote::Ident), "`a``b`");
+    }
+}
@@ -0,0 +1,306 @@
+use anyhow::{anyhow, Context, Result};
+use colored::{Color, Colorize};
+use comfy_table::Cell;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{path::PathBuf, str::FromStr};
+
+use crate::{config::Config, fl, utils::read_file};
+
+/// color of a theme item, `#rrggbb` or a name such as `red` or `bright black`,
+/// empty for no color
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ThemeColor(Option<Color>);
+
+impl ThemeColor {
+    const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self(Some(Color::TrueColor { r, g, b }))
+    }
+
+    const fn hex(hex: u32) -> Self {
+        Self::rgb((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+    }
+
+    pub fn paint(&self, text: &str) -> String {
+        match self.0 {
+            Some(color) => text.color(color).to_string(),
+            None => text.to_string(),
+        }
+    }
+
+    /// table cell in this color
+    pub fn cell(&self, text: &str) -> Cell {
+        let cell = Cell::new(text);
+        let color = match self.0 {
+            Some(color) => color,
+            None => return cell,
+        };
+        use comfy_table::Color as C;
+        cell.fg(match color {
+            Color::Black => C::Black,
+            Color::Red => C::DarkRed,
+            Color::Green => C::DarkGreen,
+            Color::Yellow => C::DarkYellow,
+            Color::Blue => C::DarkBlue,
+            Color::Magenta => C::DarkMagenta,
+            Color::Cyan => C::DarkCyan,
+            Color::White => C::Grey,
+            Color::BrightBlack => C::DarkGrey,
+            Color::BrightRed => C::Red,
+            Color::BrightGreen => C::Green,
+            Color::BrightYellow => C::Yellow,
+            Color::BrightBlue => C::Blue,
+            Color::BrightMagenta => C::Magenta,
+            Color::BrightCyan => C::Cyan,
+            Color::BrightWhite => C::White,
+            Color::TrueColor { r, g, b } => C::Rgb { r, g, b },
+        })
+    }
+}
+
+impl FromStr for ThemeColor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() || s.eq_ignore_ascii_case("none") {
+            return Ok(Self(None));
+        }
+        if let Some(hex) = s.strip_prefix('#') {
+            return match u32::from_str_radix(hex, 16) {
+                Ok(hex) if s.len() == 7 => Ok(Self::hex(hex)),
+                _ => Err(anyhow!(fl!("invalid-value", val = s))),
+            };
+        }
+        s.replace('_', " ")
+            .parse::<Color>()
+            .map(|color| Self(Some(color)))
+            .map_err(|_| anyhow!(fl!("invalid-value", val = s)))
+    }
+}
+
+impl std::fmt::Display for ThemeColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let val = match self.0 {
+            None => return Ok(()),
+            Some(Color::TrueColor { r, g, b }) => return write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+            Some(Color::Black) => "black",
+            Some(Color::Red) => "red",
+            Some(Color::Green) => "green",
+            Some(Color::Yellow) => "yellow",
+            Some(Color::Blue) => "blue",
+            Some(Color::Magenta) => "magenta",
+            Some(Color::Cyan) => "cyan",
+            Some(Color::White) => "white",
+            Some(Color::BrightBlack) => "bright black",
+            Some(Color::BrightRed) => "bright red",
+            Some(Color::BrightGreen) => "bright green",
+            Some(Color::BrightYellow) => "bright yellow",
+            Some(Color::BrightBlue) => "bright blue",
+            Some(Color::BrightMagenta) => "bright magenta",
+            Some(Color::BrightCyan) => "bright cyan",
+            Some(Color::BrightWhite) => "bright white",
+        };
+        write!(f, "{}", val)
+    }
+}
+
+impl Serialize for ThemeColor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for ThemeColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// colors of syntax highlighting, tables and shell, items missing in
+/// config or theme files are taken from monokai
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub keyword: ThemeColor,
+    pub function: ThemeColor,
+    pub identifier: ThemeColor,
+    pub string: ThemeColor,
+    pub number: ThemeColor,
+    pub comment: ThemeColor,
+    pub operator: ThemeColor,
+    /// table header
+    pub header: ThemeColor,
+    /// NULL in tables
+    pub null: ThemeColor,
+    pub prompt: ThemeColor,
+    /// prompt of guarded profiles, such as production ones
+    pub prompt_guarded: ThemeColor,
+    /// inline hints of shell
+    pub hint: ThemeColor,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::monokai()
+    }
+}
+
+/// names of themes shipped with dcli
+pub const BUILTIN_THEMES: [&str; 4] = ["monokai", "solarized", "dracula", "none"];
+
+impl Theme {
+    pub fn monokai() -> Self {
+        Self {
+            keyword: ThemeColor::rgb(94, 167, 2),
+            function: ThemeColor::rgb(0, 167, 170),
+            identifier: ThemeColor::rgb(66, 122, 179),
+            string: ThemeColor::rgb(253, 235, 97),
+            number: ThemeColor::rgb(188, 148, 183),
+            comment: ThemeColor::rgb(104, 106, 102),
+            operator: ThemeColor::rgb(245, 66, 53),
+            header: ThemeColor::rgb(207, 174, 0),
+            null: ThemeColor::rgb(104, 106, 102),
+            prompt: ThemeColor::rgb(94, 167, 2),
+            prompt_guarded: ThemeColor::rgb(216, 30, 0),
+            hint: ThemeColor::rgb(104, 106, 102),
+        }
+    }
+
+    pub fn solarized() -> Self {
+        Self {
+            keyword: ThemeColor::hex(0x859900),
+            function: ThemeColor::hex(0x6c71c4),
+            identifier: ThemeColor::hex(0x268bd2),
+            string: ThemeColor::hex(0x2aa198),
+            number: ThemeColor::hex(0xd33682),
+            comment: ThemeColor::hex(0x586e75),
+            operator: ThemeColor::hex(0xcb4b16),
+            header: ThemeColor::hex(0xb58900),
+            null: ThemeColor::hex(0x586e75),
+            prompt: ThemeColor::hex(0x859900),
+            prompt_guarded: ThemeColor::hex(0xdc322f),
+            hint: ThemeColor::hex(0x586e75),
+        }
+    }
+
+    pub fn dracula() -> Self {
+        Self {
+            keyword: ThemeColor::hex(0xff79c6),
+            function: ThemeColor::hex(0x50fa7b),
+            identifier: ThemeColor::hex(0x8be9fd),
+            string: ThemeColor::hex(0xf1fa8c),
+            number: ThemeColor::hex(0xbd93f9),
+            comment: ThemeColor::hex(0x6272a4),
+            operator: ThemeColor::hex(0xffb86c),
+            header: ThemeColor::hex(0xbd93f9),
+            null: ThemeColor::hex(0x6272a4),
+            prompt: ThemeColor::hex(0x50fa7b),
+            prompt_guarded: ThemeColor::hex(0xff5555),
+            hint: ThemeColor::hex(0x6272a4),
+        }
+    }
+
+    /// no color at all, used when `NO_COLOR` is set
+    pub fn none() -> Self {
+        let none = ThemeColor::default();
+        Self {
+            keyword: none,
+            function: none,
+            identifier: none,
+            string: none,
+            number: none,
+            comment: none,
+            operator: none,
+            header: none,
+            null: none,
+            prompt: none,
+            prompt_guarded: none,
+            hint: none,
+        }
+    }
+
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "monokai" => Some(Self::monokai()),
+            "solarized" => Some(Self::solarized()),
+            "dracula" => Some(Self::dracula()),
+            "none" => Some(Self::none()),
+            _ => None,
+        }
+    }
+}
+
+/// directory of theme files, `<name>.toml`
+pub fn themes_dir() -> Result<PathBuf> {
+    let mut path = PathBuf::from(std::env::var("HOME").with_context(|| fl!("home-not-set"))?);
+    path.push(".dcli");
+    path.push("themes");
+    Ok(path)
+}
+
+/// theme by name, looked up in builtin themes, `themes` of config, then theme files
+pub fn find(config: &Config, name: &str) -> Result<Theme> {
+    if let Some(theme) = Theme::builtin(name) {
+        return Ok(theme);
+    }
+    if let Some(theme) = config.themes.get(name) {
+        return Ok(theme.clone());
+    }
+    let mut path = themes_dir()?;
+    path.push(format!("{}.toml", name));
+    if !path.exists() {
+        return Err(anyhow!(fl!("theme-not-found", name = name)));
+    }
+    let file = path.to_string_lossy().to_string();
+    toml::from_str(&read_file(&file)?).with_context(|| fl!("theme-invalid-file", file = file))
+}
+
+/// names and sources of all themes, in builtin, config and file order
+pub fn list(config: &Config) -> Result<Vec<(String, &'static str)>> {
+    let mut themes: Vec<(String, &str)> = BUILTIN_THEMES
+        .iter()
+        .map(|name| (name.to_string(), "builtin"))
+        .collect();
+    let mut names: Vec<&String> = config.themes.keys().collect();
+    names.sort();
+    themes.extend(names.into_iter().map(|name| (name.clone(), "config")));
+    let dir = themes_dir()?;
+    if dir.exists() {
+        let mut files: Vec<String> = std::fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+            .collect();
+        files.sort();
+        themes.extend(files.into_iter().map(|name| (name, "file")));
+    }
+    Ok(themes)
+}
+
+static THEME: OnceCell<Theme> = OnceCell::new();
+
+/// set up theme selected in config, called once on start,
+/// `NO_COLOR` turns off colors whatever theme is selected
+pub fn init(config: &Config) {
+    let no_color = std::env::var("NO_COLOR").is_ok_and(|v| !v.is_empty());
+    let theme = if no_color {
+        Theme::none()
+    } else {
+        match &config.theme {
+            Some(name) => find(config, name).unwrap_or_else(|e| {
+                log::warn!("failed to load theme {}: {:#}", name, e);
+                Theme::default()
+            }),
+            None => Theme::default(),
+        }
+    };
+    THEME.set(theme).ok();
+}
+
+/// theme in use
+pub fn current() -> &'static Theme {
+    THEME.get_or_init(Theme::default)
+}
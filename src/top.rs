@@ -0,0 +1,305 @@
+use anyhow::Result;
+use comfy_table::{Attribute, Cell, Color};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute, terminal,
+};
+use std::{
+    io::{stdout, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::Config,
+    fl, guard,
+    mysql::{kill_statement, LockWait, Process, Session},
+};
+
+/// queries running longer than this are shown in yellow
+const WARN_SECS: i64 = 10;
+/// queries running longer than this are shown in red
+const CRIT_SECS: i64 = 60;
+/// max chars of query text shown in dashboard
+const INFO_WIDTH: usize = 80;
+
+/// restore terminal when dashboard exits, even on error
+struct RawModeGuard;
+
+impl RawModeGuard {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(RawModeGuard)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// a kill waiting for user confirmation
+#[derive(Debug, Clone, Copy)]
+struct PendingKill {
+    id: u64,
+    query_only: bool,
+}
+
+/// dashboard state between refreshes
+struct Dashboard {
+    processes: Vec<Process>,
+    /// error message if lock waits can not be read, such as sys schema is missing
+    lock_waits: std::result::Result<Vec<LockWait>, String>,
+    /// last `Questions` counter and when it was read
+    questions: Option<(u64, Instant)>,
+    qps: Option<f64>,
+    connected: String,
+    running: String,
+    selected: usize,
+    pending: Option<PendingKill>,
+    message: Option<String>,
+}
+
+impl Dashboard {
+    fn new() -> Self {
+        Self {
+            processes: vec![],
+            lock_waits: Ok(vec![]),
+            questions: None,
+            qps: None,
+            connected: String::new(),
+            running: String::new(),
+            selected: 0,
+            pending: None,
+            message: None,
+        }
+    }
+
+    async fn refresh(&mut self, session: &Session) -> Result<()> {
+        let status = session.global_status().await?;
+        let now = Instant::now();
+        if let Some(questions) = status.get("Questions").and_then(|q| q.parse::<u64>().ok()) {
+            if let Some((prev, at)) = self.questions {
+                let secs = now.duration_since(at).as_secs_f64();
+                if secs > 0.0 {
+                    self.qps = Some(questions.saturating_sub(prev) as f64 / secs);
+                }
+            }
+            self.questions = Some((questions, now));
+        }
+        self.connected = status.get("Threads_connected").cloned().unwrap_or_default();
+        self.running = status.get("Threads_running").cloned().unwrap_or_default();
+        self.processes = session.processes().await?;
+        self.lock_waits = session.lock_waits().await.map_err(|e| format!("{:#}", e));
+        if self.selected >= self.processes.len() {
+            self.selected = self.processes.len().saturating_sub(1);
+        }
+        Ok(())
+    }
+
+    fn render(&self, config: &Config, interval: f64) -> String {
+        let mut out = vec![
+            fl!(
+                "top-header",
+                interval = interval.to_string(),
+                time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+            ),
+            fl!(
+                "top-counters",
+                qps = self
+                    .qps
+                    .map(|q| format!("{:.1}", q))
+                    .unwrap_or_else(|| "-".to_string()),
+                connected = self.connected.clone(),
+                running = self.running.clone()
+            ),
+            String::new(),
+        ];
+
+        let mut table = config.new_table();
+        table.set_header(vec![
+            "", "ID", "USER", "HOST", "DB", "COMMAND", "TIME", "STATE", "INFO",
+        ]);
+        for (idx, p) in self.processes.iter().enumerate() {
+            let selected = idx == self.selected;
+            let mut cells = vec![
+                Cell::new(if selected { ">" } else { "" }),
+                Cell::new(p.id),
+                Cell::new(&p.user),
+                Cell::new(&p.host),
+                Cell::new(p.db.as_deref().unwrap_or("")),
+                Cell::new(&p.command),
+                Cell::new(p.time),
+                Cell::new(p.state.as_deref().unwrap_or("")),
+                Cell::new(one_line(p.info.as_deref().unwrap_or(""), INFO_WIDTH)),
+            ];
+            if let Some(color) = duration_color(p.time) {
+                cells = cells.into_iter().map(|c| c.fg(color)).collect();
+            }
+            if selected {
+                cells = cells
+                    .into_iter()
+                    .map(|c| c.add_attribute(Attribute::Reverse))
+                    .collect();
+            }
+            table.add_row(cells);
+        }
+        out.push(table.to_string());
+        out.push(String::new());
+
+        match &self.lock_waits {
+            Ok(waits) if waits.is_empty() => out.push(fl!("top-no-lock-waits")),
+            Ok(waits) => {
+                let mut table = config.new_table();
+                table.set_header(vec![
+                    "WAITING",
+                    "WAITING QUERY",
+                    "BLOCKING",
+                    "BLOCKING QUERY",
+                    "WAIT",
+                ]);
+                for w in waits {
+                    table.add_row(vec![
+                        Cell::new(w.waiting_pid),
+                        Cell::new(one_line(
+                            w.waiting_query.as_deref().unwrap_or(""),
+                            INFO_WIDTH / 2,
+                        )),
+                        Cell::new(w.blocking_pid).fg(Color::Red),
+                        Cell::new(one_line(
+                            w.blocking_query.as_deref().unwrap_or(""),
+                            INFO_WIDTH / 2,
+                        )),
+                        Cell::new(w.wait_secs),
+                    ]);
+                }
+                out.push(table.to_string());
+            }
+            Err(e) => out.push(fl!("top-lock-waits-unavailable", error = e.clone())),
+        }
+        out.push(String::new());
+
+        if let Some(pending) = self.pending {
+            out.push(if pending.query_only {
+                fl!("top-confirm-kill-query", id = pending.id.to_string())
+            } else {
+                fl!("top-confirm-kill", id = pending.id.to_string())
+            });
+        } else if let Some(msg) = &self.message {
+            out.push(msg.clone());
+        } else {
+            out.push(fl!("top-help"));
+        }
+        // raw mode does not translate \n to \r\n
+        out.join("\n").replace('\n', "\r\n")
+    }
+
+    /// handle a key press, return false if dashboard should quit
+    async fn handle_key(&mut self, session: &Session, key: KeyEvent) -> bool {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return false;
+        }
+        if let Some(pending) = self.pending.take() {
+            if let KeyCode::Char('y') | KeyCode::Char('Y') = key.code {
+                self.message = Some(match session.kill(pending.id, pending.query_only).await {
+                    Ok(_) => fl!("top-killed", id = pending.id.to_string()),
+                    Err(e) => format!("{:#}", e),
+                });
+            } else {
+                self.message = None;
+            }
+            return true;
+        }
+        self.message = None;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down if self.selected + 1 < self.processes.len() => self.selected += 1,
+            KeyCode::Char(c @ 'k') | KeyCode::Char(c @ 'K') => {
+                if let Some(p) = self.processes.get(self.selected) {
+                    // read-only profiles can't kill other sessions
+                    let sql = kill_statement(p.id, c == 'k');
+                    match guard::precheck(session.mode(), &sql, true) {
+                        Ok(_) => {
+                            self.pending = Some(PendingKill {
+                                id: p.id,
+                                query_only: c == 'k',
+                            })
+                        }
+                        Err(e) => self.message = Some(format!("{:#}", e)),
+                    }
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+}
+
+fn one_line(text: &str, width: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if text.chars().count() > width {
+        let mut text: String = text.chars().take(width.saturating_sub(3)).collect();
+        text.push_str("...");
+        text
+    } else {
+        text
+    }
+}
+
+fn duration_color(secs: i64) -> Option<Color> {
+    if secs >= CRIT_SECS {
+        Some(Color::Red)
+    } else if secs >= WARN_SECS {
+        Some(Color::Yellow)
+    } else {
+        None
+    }
+}
+
+/// refreshing dashboard of active sessions, lock waits and server counters,
+/// `k` kills selected query and `K` kills its connection after confirmation
+pub async fn top(config: &Config, session: &Session, interval: f64) -> Result<()> {
+    let interval_dur = Duration::from_secs_f64(interval.max(0.5));
+    let mut dashboard = Dashboard::new();
+    let _guard = RawModeGuard::enter()?;
+    let mut last_refresh: Option<Instant> = None;
+    let mut redraw = true;
+    loop {
+        if last_refresh.map_or(true, |at| at.elapsed() >= interval_dur) {
+            dashboard.refresh(session).await?;
+            last_refresh = Some(Instant::now());
+            redraw = true;
+        }
+        if redraw {
+            let mut out = stdout();
+            execute!(
+                out,
+                cursor::MoveTo(0, 0),
+                terminal::Clear(terminal::ClearType::All)
+            )?;
+            write!(out, "{}", dashboard.render(config, interval))?;
+            out.flush()?;
+            redraw = false;
+        }
+        if let Some(key) = tokio::task::spawn_blocking(read_key).await?? {
+            if !dashboard.handle_key(session, key).await {
+                return Ok(());
+            }
+            redraw = true;
+        }
+    }
+}
+
+/// wait a moment for a key press, blocks so it runs on blocking threads
+fn read_key() -> Result<Option<KeyEvent>> {
+    if event::poll(Duration::from_millis(100))? {
+        if let Event::Key(key) = event::read()? {
+            return Ok(Some(key));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,154 @@
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    io::Read,
+    net::{TcpListener, TcpStream},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::{KnownHosts, Profile},
+    fl,
+};
+
+/// how long to wait for ssh to authenticate and start forwarding
+const OPEN_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// opened tunnels keyed by jump host and target, a tunnel is closed when
+/// the last session or command using it is dropped
+static TUNNELS: Lazy<Mutex<HashMap<String, Weak<Tunnel>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// local port forwarded to database through SSH jump host,
+/// backed by a `ssh -N -L` child process
+pub struct Tunnel {
+    pub local_port: u16,
+    child: Mutex<Child>,
+}
+
+impl Tunnel {
+    /// reuse opened tunnel to the same target or start a new one
+    pub fn open(profile: &Profile) -> Result<Arc<Self>> {
+        let jump = profile
+            .ssh_host
+            .as_ref()
+            .ok_or_else(|| anyhow!(fl!("ssh-host-not-set")))?;
+        let ssh_port = profile.ssh_port.unwrap_or(22);
+        // a socket path is forwarded as is, it is resolved on jump host
+        let target = match &profile.socket {
+            Some(socket) => socket.to_string_lossy().to_string(),
+            None => format!("{}:{}", profile.host, profile.port),
+        };
+        let key = format!(
+            "{}@{}:{}->{}",
+            profile.ssh_user.clone().unwrap_or_default(),
+            jump,
+            ssh_port,
+            target
+        );
+        let mut tunnels = TUNNELS.lock().unwrap();
+        if let Some(tunnel) = tunnels.get(&key).and_then(Weak::upgrade) {
+            return Ok(tunnel);
+        }
+        let local_port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .with_context(|| fl!("ssh-tunnel-failed", host = jump.as_str()))?
+            .port();
+        let mut command = Command::new("ssh");
+        command
+            .arg("-N")
+            .args(["-L", &format!("127.0.0.1:{}:{}", local_port, target)])
+            .args(["-p", &ssh_port.to_string()])
+            .args(["-o", "BatchMode=yes"])
+            .args(["-o", "ExitOnForwardFailure=yes"])
+            .args(["-o", "ServerAliveInterval=30"]);
+        let policy = match profile.ssh_known_hosts.unwrap_or_default() {
+            KnownHosts::Strict => "yes",
+            KnownHosts::AcceptNew => "accept-new",
+            KnownHosts::Off => "no",
+        };
+        command.args(["-o", &format!("StrictHostKeyChecking={}", policy)]);
+        if let KnownHosts::Off = profile.ssh_known_hosts.unwrap_or_default() {
+            command.args(["-o", "UserKnownHostsFile=/dev/null"]);
+        }
+        if let Some(user) = &profile.ssh_user {
+            command.args(["-l", user]);
+        }
+        if let Some(identity) = &profile.ssh_key {
+            command
+                .arg("-i")
+                .arg(identity)
+                .args(["-o", "IdentitiesOnly=yes"]);
+        }
+        command
+            .arg(jump)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        log::info!("open ssh tunnel {} on port {}", key, local_port);
+        let child = command
+            .spawn()
+            .with_context(|| fl!("launch-process-failed"))?;
+        let tunnel = Arc::new(Self {
+            local_port,
+            child: Mutex::new(child),
+        });
+        tunnel
+            .wait_ready()
+            .with_context(|| fl!("ssh-tunnel-failed", host = jump.as_str()))?;
+        tunnels.retain(|_, t| t.strong_count() > 0);
+        tunnels.insert(key, Arc::downgrade(&tunnel));
+        Ok(tunnel)
+    }
+
+    /// wait until local port accepts connection, fail if ssh exits before that
+    fn wait_ready(&self) -> Result<()> {
+        let started = Instant::now();
+        loop {
+            {
+                let mut child = self.child.lock().unwrap();
+                if let Some(status) = child.try_wait()? {
+                    let mut stderr = String::new();
+                    if let Some(mut err) = child.stderr.take() {
+                        err.read_to_string(&mut stderr)?;
+                    }
+                    return Err(anyhow!("ssh exited with {}: {}", status, stderr.trim()));
+                }
+            }
+            if TcpStream::connect(("127.0.0.1", self.local_port)).is_ok() {
+                return Ok(());
+            }
+            if started.elapsed() > OPEN_TIMEOUT {
+                return Err(anyhow!(fl!("ssh-tunnel-timeout")));
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    fn close(&self) {
+        let mut child = self.child.lock().unwrap();
+        if let Ok(None) = child.try_wait() {
+            log::info!("close ssh tunnel on port {}", self.local_port);
+            child.kill().ok();
+            child.wait().ok();
+        }
+    }
+}
+
+impl Drop for Tunnel {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// close tunnels still opened, called before exit
+pub fn close_all() {
+    let mut tunnels = TUNNELS.lock().unwrap();
+    for tunnel in tunnels.values().filter_map(Weak::upgrade) {
+        tunnel.close();
+    }
+    tunnels.clear();
+}
@@ -1,5 +1,6 @@
 use crate::{config::Lang, fl, Translations};
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone};
 use i18n_embed::{
     fluent::{fluent_language_loader, FluentLanguageLoader},
     unic_langid::LanguageIdentifier,
@@ -26,3 +27,182 @@ pub fn reset_loader(lang: &Lang) {
     language_loader.set_use_isolating(false);
     *crate::LOADER.lock().unwrap() = language_loader
 }
+
+/// split sql text into statements by `;`, semicolons in quotes or comments are ignored,
+/// statements only contain whitespaces and comments are dropped, executable comments
+/// such as `/*!40101 SET NAMES utf8mb4 */` are kept as code
+pub fn split_sql(content: &str) -> Vec<String> {
+    split_sql_chunks(content)
+        .into_iter()
+        .filter(|chunk| chunk.has_code)
+        .map(|chunk| chunk.text)
+        .collect()
+}
+
+/// text between two `;` of sql, or after the last one
+#[derive(Debug, PartialEq)]
+pub struct SqlChunk {
+    /// trimmed text without `;`
+    pub text: String,
+    /// false if it only contains comments
+    pub has_code: bool,
+    /// whether it ends with `;`
+    pub terminated: bool,
+}
+
+/// split sql text by `;` like `split_sql`, but comment only chunks are kept,
+/// blank ones are skipped
+pub fn split_sql_chunks(content: &str) -> Vec<SqlChunk> {
+    let mut chunks = vec![];
+    let mut current = String::new();
+    let mut has_code = false;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                has_code = true;
+                current.push(c);
+                while let Some(n) = chars.next() {
+                    current.push(n);
+                    if n == '\\' && c != '`' {
+                        if let Some(escaped) = chars.next() {
+                            current.push(escaped);
+                        }
+                    } else if n == c {
+                        // doubled quote is an escaped quote
+                        if chars.peek() == Some(&c) {
+                            current.push(chars.next().unwrap());
+                        } else {
+                            break;
+                        }
+                    }
+                }
+            }
+            '#' => {
+                current.push(c);
+                for n in chars.by_ref() {
+                    current.push(n);
+                    if n == '\n' {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                current.push(c);
+                current.push(chars.next().unwrap());
+                if chars.peek().map(|n| n.is_whitespace()).unwrap_or(true) {
+                    for n in chars.by_ref() {
+                        current.push(n);
+                        if n == '\n' {
+                            break;
+                        }
+                    }
+                } else {
+                    has_code = true;
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                current.push(c);
+                current.push(chars.next().unwrap());
+                if chars.peek() == Some(&'!') {
+                    has_code = true;
+                }
+                let mut prev = ' ';
+                for n in chars.by_ref() {
+                    current.push(n);
+                    if prev == '*' && n == '/' {
+                        break;
+                    }
+                    prev = n;
+                }
+            }
+            ';' => {
+                if has_code || !current.trim().is_empty() {
+                    chunks.push(SqlChunk {
+                        text: current.trim().to_string(),
+                        has_code,
+                        terminated: true,
+                    });
+                }
+                current.clear();
+                has_code = false;
+            }
+            c => {
+                if !c.is_whitespace() {
+                    has_code = true;
+                }
+                current.push(c);
+            }
+        }
+    }
+    if has_code || !current.trim().is_empty() {
+        chunks.push(SqlChunk {
+            text: current.trim().to_string(),
+            has_code,
+            terminated: false,
+        });
+    }
+    chunks
+}
+
+/// match text against shell style pattern, `*` for any chars and `?` for one char
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // position of last `*` in pattern and text position it was tried at
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((sp, st)) = star {
+            p = sp + 1;
+            t = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// parse `2021-05-01`, `2021-05-01 08:00:00`, RFC 3339 time or a duration ago such as `30m`, `2h`, `7d`
+pub fn parse_time(s: &str) -> Result<DateTime<FixedOffset>> {
+    let invalid = || anyhow!(fl!("invalid-value", val = s));
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Ok(time);
+    }
+    let local = |naive: NaiveDateTime| {
+        Local
+            .from_local_datetime(&naive)
+            .single()
+            .map(|t| t.fixed_offset())
+            .ok_or_else(invalid)
+    };
+    if let Ok(naive) = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
+        return local(naive);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return local(date.and_hms_opt(0, 0, 0).ok_or_else(invalid)?);
+    }
+    let unit = s.chars().last().ok_or_else(invalid)?;
+    let num: i64 = s[..s.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    let ago = match unit {
+        's' => Duration::seconds(num),
+        'm' => Duration::minutes(num),
+        'h' => Duration::hours(num),
+        'd' => Duration::days(num),
+        _ => return Err(invalid()),
+    };
+    Ok((Local::now() - ago).fixed_offset())
+}
@@ -0,0 +1,189 @@
+use anyhow::{anyhow, Result};
+use comfy_table::{Cell, Color};
+use std::{str::FromStr, time::Duration};
+use structopt::StructOpt;
+
+use crate::{config::Config, fl, mysql::Session};
+
+/// exit condition of watch, such as `count = 0`, checked against the first row
+#[derive(Debug, Clone)]
+pub struct Condition {
+    pub column: String,
+    pub op: String,
+    pub value: String,
+}
+
+const OPERATORS: [&str; 7] = ["!=", "<>", ">=", "<=", "=", ">", "<"];
+
+impl FromStr for Condition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        for op in OPERATORS.iter() {
+            if let Some((column, value)) = s.split_once(op) {
+                let column = column.trim();
+                let value = value.trim().trim_matches('\'').trim_matches('"');
+                if column.is_empty() {
+                    break;
+                }
+                return Ok(Condition {
+                    column: column.to_string(),
+                    op: op.to_string(),
+                    value: value.to_string(),
+                });
+            }
+        }
+        Err(anyhow!(fl!("invalid-value", val = s)))
+    }
+}
+
+impl Condition {
+    /// compare as number if both sides are numeric, else as string
+    pub fn is_met(&self, header: &[String], values: &[Vec<String>]) -> bool {
+        let idx = match header
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(&self.column))
+        {
+            Some(idx) => idx,
+            None => return false,
+        };
+        let actual = match values.first().and_then(|row| row.get(idx)) {
+            Some(actual) => actual,
+            None => return false,
+        };
+        let ord = match (actual.parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.partial_cmp(&b),
+            _ => Some(actual.as_str().cmp(self.value.as_str())),
+        };
+        let ord = match ord {
+            Some(ord) => ord,
+            None => return false,
+        };
+        match self.op.as_str() {
+            "=" => ord.is_eq(),
+            "!=" | "<>" => ord.is_ne(),
+            ">" => ord.is_gt(),
+            "<" => ord.is_lt(),
+            ">=" => ord.is_ge(),
+            "<=" => ord.is_le(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub struct WatchOptions {
+    #[cfg_attr(feature = "zh-CN", doc = "刷新间隔秒数")]
+    #[cfg_attr(feature = "en-US", doc = "seconds to wait between runs")]
+    #[structopt(short = "n", long, default_value = "2")]
+    pub interval: f64,
+
+    #[cfg_attr(feature = "zh-CN", doc = "为数值列显示与上次结果的差值")]
+    #[cfg_attr(feature = "en-US", doc = "show delta column for numeric values")]
+    #[structopt(short, long)]
+    pub delta: bool,
+
+    #[cfg_attr(feature = "zh-CN", doc = "退出条件, 如 \"count = 0\", 作用于第一行")]
+    #[cfg_attr(
+        feature = "en-US",
+        doc = "exit condition checked on first row, such as \"count = 0\""
+    )]
+    #[structopt(short, long)]
+    pub until: Option<Condition>,
+}
+
+/// build table of current result, highlight cells changed since previous result
+fn render(
+    config: &Config,
+    header: &[String],
+    values: &[Vec<String>],
+    previous: Option<&Vec<Vec<String>>>,
+    delta: bool,
+) -> String {
+    let mut table = config.new_table();
+    let prev_row = |idx: usize| previous.and_then(|p| p.get(idx));
+    // numeric columns are decided by the first row
+    let numeric: Vec<bool> = match values.first() {
+        Some(row) => row.iter().map(|v| v.parse::<f64>().is_ok()).collect(),
+        None => vec![],
+    };
+    let mut header_cells: Vec<Cell> = vec![];
+    for (idx, name) in header.iter().enumerate() {
+        header_cells.push(Cell::new(name));
+        if delta && numeric.get(idx) == Some(&true) {
+            header_cells.push(Cell::new(format!("Δ {}", name)));
+        }
+    }
+    table.set_header(header_cells);
+    for (row_idx, row) in values.iter().enumerate() {
+        let mut cells: Vec<Cell> = vec![];
+        for (idx, val) in row.iter().enumerate() {
+            let prev = prev_row(row_idx).and_then(|r| r.get(idx));
+            let cell = Cell::new(val);
+            let changed = previous.is_some() && prev != Some(val);
+            cells.push(if changed {
+                cell.fg(Color::Yellow)
+            } else {
+                cell
+            });
+            if delta && numeric.get(idx) == Some(&true) {
+                let diff = match (val.parse::<f64>(), prev.map(|p| p.parse::<f64>())) {
+                    (Ok(cur), Some(Ok(prev))) => cur - prev,
+                    _ => 0.0,
+                };
+                let cell = Cell::new(format!("{:+}", diff));
+                cells.push(if diff > 0.0 {
+                    cell.fg(Color::Green)
+                } else if diff < 0.0 {
+                    cell.fg(Color::Red)
+                } else {
+                    cell
+                });
+            }
+        }
+        table.add_row(cells);
+    }
+    table.to_string()
+}
+
+/// re-run sql every interval and redraw result in place, stop on Ctrl-C or exit condition met
+pub async fn watch(
+    config: &Config,
+    session: &Session,
+    sql: &str,
+    options: &WatchOptions,
+) -> Result<()> {
+    let interval = Duration::from_secs_f64(options.interval.max(0.1));
+    let mut previous: Option<Vec<Vec<String>>> = None;
+    loop {
+        let output = session.query(sql).await?;
+        let header = output.header();
+        let values = output.values();
+        let table = render(config, &header, &values, previous.as_ref(), options.delta);
+        // clear screen and move cursor to top left
+        print!("\x1b[2J\x1b[H");
+        println!(
+            "{}",
+            fl!(
+                "watch-header",
+                interval = options.interval.to_string(),
+                time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+            )
+        );
+        println!("{}\n", sql);
+        println!("{}", table);
+        if let Some(cond) = &options.until {
+            if cond.is_met(&header, &values) {
+                println!("{}", fl!("watch-condition-met"));
+                return Ok(());
+            }
+        }
+        previous = Some(values);
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => {
+                return Ok(());
+            }
+        }
+    }
+}